@@ -0,0 +1,85 @@
+use crate::{color::Color, image::Image};
+
+#[derive(Clone, Copy)]
+pub struct AdaptiveSampling {
+    pub min_samples: i32,
+    pub max_samples: i32,
+    pub threshold: f64,
+}
+
+impl AdaptiveSampling {
+    pub fn new(min_samples: i32, max_samples: i32, threshold: f64) -> Self {
+        let min_samples = min_samples.max(2);
+        Self {
+            min_samples,
+            max_samples: max_samples.max(min_samples),
+            threshold,
+        }
+    }
+
+    pub fn converged(&self, stats: &PixelStats) -> bool {
+        if stats.count < self.min_samples as u32 {
+            return false;
+        }
+        if stats.count >= self.max_samples as u32 {
+            return true;
+        }
+        stats.confidence_interval() <= self.threshold * stats.mean.max(1e-3)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct PixelStats {
+    pub count: u32,
+    pub mean: f64,
+    pub m2: f64,
+}
+
+impl PixelStats {
+    pub fn add(&mut self, color: &Color) {
+        let x = color.luminance();
+        self.count += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    pub fn merge(&mut self, other: &PixelStats) {
+        if other.count == 0 {
+            return;
+        }
+        let (na, nb) = (self.count as f64, other.count as f64);
+        let n = na + nb;
+        let delta = other.mean - self.mean;
+        self.mean += delta * nb / n;
+        self.m2 += other.m2 + delta * delta * na * nb / n;
+        self.count += other.count;
+    }
+
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 {
+            0.0
+        } else {
+            self.m2 / (self.count - 1) as f64
+        }
+    }
+
+    pub fn confidence_interval(&self) -> f64 {
+        if self.count == 0 {
+            return f64::INFINITY;
+        }
+        1.96 * (self.variance() / self.count as f64).sqrt()
+    }
+}
+
+pub fn sample_heatmap(counts: &[u32], width: usize, height: usize) -> Image {
+    let max = counts.iter().copied().max().unwrap_or(0).max(1) as f64;
+    Image {
+        width,
+        height,
+        pixels: counts
+            .iter()
+            .map(|&count| Color::heatmap(count as f64 / max))
+            .collect(),
+    }
+}
@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use crate::{
+    animation::{ObjectTracks, Transform},
+    fingerprint::Fingerprint,
+    hittable::{HitRecord, Hittable},
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    vec3::Vec3,
+};
+
+// Places an object by its keyframed transform. Rays carry their time within the shutter in
+// seconds, so moving objects blur and show rolling shutter skew.
+pub struct Animated {
+    object: Arc<dyn Hittable>,
+    tracks: ObjectTracks,
+    frame: f64,
+    fps: f64,
+}
+
+impl Animated {
+    pub fn new(object: Arc<dyn Hittable>, tracks: ObjectTracks, frame: f64, fps: f64) -> Self {
+        Self {
+            object,
+            tracks,
+            frame,
+            fps,
+        }
+    }
+
+    fn rotate_y(vector: &Vec3, degrees: f64) -> Vec3 {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Vec3::new(
+            cos * vector.x + sin * vector.z,
+            vector.y,
+            -sin * vector.x + cos * vector.z,
+        )
+    }
+}
+
+impl Hittable for Animated {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
+        let Transform {
+            translate,
+            rotate,
+            scale,
+        } = self.tracks.sample(self.frame + ray.time * self.fps);
+
+        // Scaling origin and direction alike keeps the ray parameter of every hit unchanged.
+        let origin = Self::rotate_y(&(&ray.origin - &translate), -rotate) / scale;
+        let direction = Self::rotate_y(&ray.direction, -rotate) / scale;
+        let local = Ray::with_time(origin, direction, ray.time);
+
+        let hit = self.object.hit(&local, interval)?;
+        Some(HitRecord {
+            point: Self::rotate_y(&(&hit.point * scale), rotate) + &translate,
+            normal: Self::rotate_y(&hit.normal, rotate),
+            ..hit
+        })
+    }
+
+    fn material(&self) -> Option<Arc<dyn Material>> {
+        self.object.material()
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_str("Animated");
+        fingerprint.write_f64(self.frame);
+        fingerprint.write_f64(self.fps);
+        fingerprint.write_str(self.tracks.translate.interpolation.name());
+        for (frame, value) in &self.tracks.translate.keys {
+            fingerprint.write_f64(*frame);
+            fingerprint.write_vec3(value);
+        }
+        for track in [&self.tracks.rotate, &self.tracks.scale] {
+            fingerprint.write_str(track.interpolation.name());
+            for (frame, value) in &track.keys {
+                fingerprint.write_f64(*frame);
+                fingerprint.write_f64(*value);
+            }
+        }
+        self.object.fingerprint(fingerprint);
+    }
+}
@@ -0,0 +1,322 @@
+use std::{fs, io, sync::Arc};
+
+use crate::{
+    animated::Animated,
+    camera::Camera,
+    hittable_list::HittableList,
+    vec3::{Point3, Vec3},
+};
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    Cubic,
+}
+
+impl Interpolation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Interpolation::Linear => "linear",
+            Interpolation::Cubic => "cubic",
+        }
+    }
+}
+
+impl std::str::FromStr for Interpolation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(Interpolation::Linear),
+            "cubic" => Ok(Interpolation::Cubic),
+            _ => Err(format!(
+                "unknown interpolation '{s}' (expected linear or cubic)"
+            )),
+        }
+    }
+}
+
+// Values that keyframes can blend, expressed as weighted sums of keys.
+pub trait Keyable: Clone {
+    fn combine(terms: &[(f64, &Self)]) -> Self;
+}
+
+impl Keyable for f64 {
+    fn combine(terms: &[(f64, &Self)]) -> Self {
+        terms.iter().map(|(weight, value)| weight * *value).sum()
+    }
+}
+
+impl Keyable for Vec3 {
+    fn combine(terms: &[(f64, &Self)]) -> Self {
+        terms.iter().fold(Vec3::default(), |sum, (weight, value)| {
+            sum + *value * *weight
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct Track<T> {
+    pub keys: Vec<(f64, T)>,
+    pub interpolation: Interpolation,
+}
+
+impl<T> Default for Track<T> {
+    fn default() -> Self {
+        Self {
+            keys: Vec::new(),
+            interpolation: Interpolation::default(),
+        }
+    }
+}
+
+impl<T: Keyable> Track<T> {
+    pub fn insert(&mut self, frame: f64, value: T) {
+        let index = self.keys.partition_point(|(key, _)| *key <= frame);
+        if index > 0 && self.keys[index - 1].0 == frame {
+            self.keys[index - 1].1 = value;
+        } else {
+            self.keys.insert(index, (frame, value));
+        }
+    }
+
+    // Holds the first and last keys outside the keyed range. Cubic tracks follow a Catmull-Rom
+    // spline whose tangents account for uneven key spacing.
+    pub fn sample(&self, frame: f64) -> Option<T> {
+        let (first, last) = (self.keys.first()?, self.keys.last()?);
+        if frame <= first.0 {
+            return Some(first.1.clone());
+        }
+        if frame >= last.0 {
+            return Some(last.1.clone());
+        }
+        let i = self.keys.partition_point(|(key, _)| *key <= frame) - 1;
+        let ((f0, p0), (f1, p1)) = (&self.keys[i], &self.keys[i + 1]);
+        let span = f1 - f0;
+        let t = (frame - f0) / span;
+        if self.interpolation == Interpolation::Linear {
+            return Some(T::combine(&[(1.0 - t, p0), (t, p1)]));
+        }
+
+        let (fa, pa) = &self.keys[i.saturating_sub(1)];
+        let (fb, pb) = &self.keys[(i + 2).min(self.keys.len() - 1)];
+        let (t2, t3) = (t * t, t * t * t);
+        let h00 = 2.0 * t3 - 3.0 * t2 + 1.0;
+        let h10 = (t3 - 2.0 * t2 + t) * span;
+        let h01 = -2.0 * t3 + 3.0 * t2;
+        let h11 = (t3 - t2) * span;
+        // Tangents are finite differences over the neighbouring keys: (p1 - pa) / (f1 - fa) at
+        // the start and (pb - p0) / (fb - f0) at the end of the span.
+        let m0 = h10 / (f1 - fa);
+        let m1 = h11 / (fb - f0);
+        Some(T::combine(&[
+            (h00 - m1, p0),
+            (h01 + m0, p1),
+            (-m0, pa),
+            (m1, pb),
+        ]))
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct ObjectTracks {
+    pub translate: Track<Vec3>,
+    pub rotate: Track<f64>,
+    pub scale: Track<f64>,
+}
+
+// Object transform at one instant: uniform scale, then rotation about the y axis in degrees,
+// then translation. Scale and rotation pivot on the world origin, as objects are modelled in
+// world space.
+#[derive(Clone)]
+pub struct Transform {
+    pub translate: Vec3,
+    pub rotate: f64,
+    pub scale: f64,
+}
+
+impl ObjectTracks {
+    pub fn sample(&self, frame: f64) -> Transform {
+        Transform {
+            translate: self.translate.sample(frame).unwrap_or_default(),
+            rotate: self.rotate.sample(frame).unwrap_or(0.0),
+            scale: self.scale.sample(frame).unwrap_or(1.0),
+        }
+    }
+}
+
+// Keyframes read from a text file with one statement per line:
+//
+//     fps 24
+//     frames 1 48
+//     camera.position <frame> <x> <y> <z>
+//     camera.target <frame> <x> <y> <z>
+//     camera.fov <frame> <degrees>
+//     object.<index>.translate <frame> <x> <y> <z>
+//     object.<index>.rotate <frame> <degrees>
+//     object.<index>.scale <frame> <factor>
+//     interpolation <track> linear|cubic
+//
+// Objects are addressed by their index in the scene, the same as in the object ID AOV.
+pub struct Animation {
+    pub fps: f64,
+    pub frames: (u32, u32),
+    pub camera_position: Track<Vec3>,
+    pub camera_target: Track<Vec3>,
+    pub camera_fov: Track<f64>,
+    pub objects: Vec<(usize, ObjectTracks)>,
+}
+
+impl Default for Animation {
+    fn default() -> Self {
+        Self {
+            fps: 24.0,
+            frames: (1, 1),
+            camera_position: Track::default(),
+            camera_target: Track::default(),
+            camera_fov: Track::default(),
+            objects: Vec::new(),
+        }
+    }
+}
+
+impl Animation {
+    pub fn load(path: &str) -> io::Result<Self> {
+        let mut animation = Animation::default();
+        for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.is_empty() {
+                continue;
+            }
+            animation.parse_line(&fields).map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{path}:{}: {err}", number + 1),
+                )
+            })?;
+        }
+        Ok(animation)
+    }
+
+    pub fn camera(&self, camera: &Camera, frame: f64) -> Camera {
+        let mut camera = camera.clone();
+        if let Some(fov) = self.camera_fov.sample(frame) {
+            camera = camera.with_vfov(fov);
+        }
+        let position = self.camera_position.sample(frame);
+        let target = self.camera_target.sample(frame);
+        if position.is_some() || target.is_some() {
+            camera = camera.with_view(
+                position.unwrap_or_default(),
+                target.unwrap_or(Point3::new(0.0, 0.0, -1.0)),
+                Vec3::new(0.0, 1.0, 0.0),
+            );
+        }
+        camera
+    }
+
+    pub fn world(&self, world: &HittableList, frame: f64) -> HittableList {
+        let objects = world.objects.iter().enumerate().map(|(index, object)| {
+            match self.objects.iter().find(|(animated, _)| *animated == index) {
+                Some((_, tracks)) => Arc::new(Animated::new(
+                    object.clone(),
+                    tracks.clone(),
+                    frame,
+                    self.fps,
+                )),
+                None => object.clone(),
+            }
+        });
+        HittableList::new(objects.collect())
+    }
+
+    fn parse_line(&mut self, fields: &[&str]) -> Result<(), String> {
+        let number = |value: &str| {
+            value
+                .parse::<f64>()
+                .map_err(|err| format!("invalid number '{value}': {err}"))
+        };
+        match fields {
+            ["fps", fps] => self.fps = number(fps)?,
+            ["frames", start, end] => {
+                let frame = |value: &str| {
+                    value
+                        .parse::<u32>()
+                        .map_err(|err| format!("invalid frame '{value}': {err}"))
+                };
+                self.frames = (frame(start)?, frame(end)?);
+            }
+            ["interpolation", track, interpolation] => {
+                let interpolation = interpolation.parse()?;
+                match self.track(track)? {
+                    TrackRef::Vector(track) => track.interpolation = interpolation,
+                    TrackRef::Scalar(track) => track.interpolation = interpolation,
+                }
+            }
+            [track, frame, values @ ..] => {
+                let frame = number(frame)?;
+                let values = values
+                    .iter()
+                    .map(|value| number(value))
+                    .collect::<Result<Vec<_>, _>>()?;
+                // Object scales divide ray coordinates, so zero would lose the object and a
+                // negative factor would mirror it.
+                if track.starts_with("object.")
+                    && track.ends_with(".scale")
+                    && values
+                        .iter()
+                        .any(|scale| !(scale.is_finite() && *scale > 0.0))
+                {
+                    return Err(format!("scale in '{}' must be positive", fields.join(" ")));
+                }
+                match (self.track(track)?, &values[..]) {
+                    (TrackRef::Vector(track), [x, y, z]) => {
+                        track.insert(frame, Vec3::new(*x, *y, *z))
+                    }
+                    (TrackRef::Scalar(track), [value]) => track.insert(frame, *value),
+                    _ => return Err(format!("wrong number of values for '{}'", fields[0])),
+                }
+            }
+            _ => return Err(format!("invalid statement '{}'", fields.join(" "))),
+        }
+        Ok(())
+    }
+
+    fn track(&mut self, name: &str) -> Result<TrackRef<'_>, String> {
+        if let Some(rest) = name.strip_prefix("object.") {
+            let (index, property) = rest
+                .split_once('.')
+                .ok_or_else(|| format!("invalid track '{name}'"))?;
+            let index = index
+                .parse()
+                .map_err(|_| format!("invalid object index in '{name}'"))?;
+            let position = match self.objects.iter().position(|(object, _)| *object == index) {
+                Some(position) => position,
+                None => {
+                    self.objects.push((index, ObjectTracks::default()));
+                    self.objects.len() - 1
+                }
+            };
+            let tracks = &mut self.objects[position].1;
+            return match property {
+                "translate" => Ok(TrackRef::Vector(&mut tracks.translate)),
+                "rotate" => Ok(TrackRef::Scalar(&mut tracks.rotate)),
+                "scale" => Ok(TrackRef::Scalar(&mut tracks.scale)),
+                _ => Err(format!("unknown object track '{name}'")),
+            };
+        }
+        match name {
+            "camera.position" => Ok(TrackRef::Vector(&mut self.camera_position)),
+            "camera.target" => Ok(TrackRef::Vector(&mut self.camera_target)),
+            "camera.fov" => Ok(TrackRef::Scalar(&mut self.camera_fov)),
+            _ => Err(format!("unknown track '{name}'")),
+        }
+    }
+}
+
+enum TrackRef<'a> {
+    Vector(&'a mut Track<Vec3>),
+    Scalar(&'a mut Track<f64>),
+}
@@ -0,0 +1,119 @@
+use std::{fs::File, io, io::BufWriter};
+
+use crate::{
+    color::Color,
+    hittable::HitRecord,
+    image::{Image, Rect},
+    integrator::sky,
+    ray::Ray,
+    vec3::Vec3,
+};
+
+pub struct Aovs {
+    pub albedo: Image,
+    pub normal: Image,
+    pub depth: Image,
+    pub object_id: Image,
+    pub material_id: Image,
+}
+
+impl Aovs {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            albedo: Image::new(width, height),
+            normal: Image::new(width, height),
+            depth: Image::new(width, height),
+            object_id: Image::new(width, height),
+            material_id: Image::new(width, height),
+        }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, pixel: &AovPixel) {
+        let hits = pixel.hits.max(1) as f64;
+        let samples = pixel.samples.max(1) as f64;
+        let depth = if pixel.hits > 0 {
+            pixel.depth / hits
+        } else {
+            f64::INFINITY
+        };
+        self.albedo
+            .set(x, y, pixel.albedo.clone() * (1.0 / samples));
+        self.normal.set(x, y, Color(&pixel.normal / hits));
+        self.depth.set(x, y, Color::new(depth, depth, depth));
+        let object_id = pixel.object_id.map_or(0.0, |id| (id + 1) as f64);
+        self.object_id
+            .set(x, y, Color::new(object_id, object_id, object_id));
+        let material_id = pixel.material.map_or(0.0, |id| (id + 1) as f64);
+        self.material_id
+            .set(x, y, Color::new(material_id, material_id, material_id));
+    }
+
+    pub fn crop(&self, rect: &Rect) -> Aovs {
+        Aovs {
+            albedo: self.albedo.crop(rect),
+            normal: self.normal.crop(rect),
+            depth: self.depth.crop(rect),
+            object_id: self.object_id.crop(rect),
+            material_id: self.material_id.crop(rect),
+        }
+    }
+
+    pub fn layers(&self) -> [(&'static str, &Image); 5] {
+        [
+            ("albedo", &self.albedo),
+            ("normal", &self.normal),
+            ("depth", &self.depth),
+            ("object_id", &self.object_id),
+            ("material_id", &self.material_id),
+        ]
+    }
+
+    pub fn save(&self, prefix: &str) -> io::Result<()> {
+        for (name, image) in self.layers() {
+            let mut out = BufWriter::new(File::create(format!("{prefix}.{name}.pfm"))?);
+            image.write_pfm(&mut out)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct AovPixel {
+    pub albedo: Color,
+    pub normal: Vec3,
+    pub depth: f64,
+    pub object_id: Option<usize>,
+    pub material: Option<usize>,
+    pub hits: u32,
+    pub samples: u32,
+}
+
+impl AovPixel {
+    pub fn add(&mut self, ray: &Ray, hit: Option<&HitRecord>) {
+        self.samples += 1;
+        let Some(hit) = hit else {
+            self.albedo += sky(ray);
+            return;
+        };
+        self.hits += 1;
+        self.albedo += hit.material.albedo(hit);
+        self.normal += hit.normal.clone();
+        self.depth += hit.time * ray.direction.len();
+        if self.object_id.is_none() {
+            self.object_id = Some(hit.object_id);
+            self.material = hit.material_id;
+        }
+    }
+
+    pub fn merge(&mut self, other: &AovPixel) {
+        self.albedo += other.albedo.clone();
+        self.normal += other.normal.clone();
+        self.depth += other.depth;
+        self.hits += other.hits;
+        self.samples += other.samples;
+        if self.object_id.is_none() {
+            self.object_id = other.object_id;
+            self.material = other.material;
+        }
+    }
+}
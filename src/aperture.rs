@@ -0,0 +1,190 @@
+use std::{f64::consts::PI, fs::File, io, io::BufReader, sync::Arc};
+
+use crate::{fingerprint::Fingerprint, image::Image, vec3::Vec3};
+
+#[derive(Clone, Default, PartialEq)]
+pub enum ApertureKind {
+    #[default]
+    Circle,
+    Polygon {
+        blades: u32,
+        rotation: f64,
+    },
+    Image(String),
+}
+
+impl ApertureKind {
+    pub fn build(&self) -> io::Result<Aperture> {
+        Ok(match self {
+            ApertureKind::Circle => Aperture::Circle,
+            ApertureKind::Polygon { blades, rotation } => Aperture::Polygon {
+                blades: *blades,
+                rotation: rotation.to_radians(),
+            },
+            ApertureKind::Image(path) => {
+                let image = Image::read_pnm(&mut BufReader::new(File::open(path)?))?;
+                Aperture::Mask(Arc::new(ApertureMask::new(&image)?))
+            }
+        })
+    }
+}
+
+impl std::str::FromStr for ApertureKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(3, ':');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some("circle"), None, None) => Ok(ApertureKind::Circle),
+            (Some("polygon"), Some(blades), rotation) => {
+                let blades = blades
+                    .parse()
+                    .ok()
+                    .filter(|blades| *blades >= 3)
+                    .ok_or_else(|| {
+                        format!("invalid blade count '{blades}' (expected 3 or more)")
+                    })?;
+                let rotation = match rotation {
+                    Some(rotation) => rotation
+                        .parse()
+                        .map_err(|err| format!("invalid rotation '{rotation}': {err}"))?,
+                    None => 0.0,
+                };
+                Ok(ApertureKind::Polygon { blades, rotation })
+            }
+            (Some("image"), Some(_), _) => Ok(ApertureKind::Image(s["image:".len()..].to_string())),
+            _ => Err(format!(
+                "unknown aperture '{s}' (expected circle, polygon:blades[:rotation] or image:path)"
+            )),
+        }
+    }
+}
+
+// Aperture shapes map a 2D sample to a point on the lens within the unit disk.
+#[derive(Clone, Default)]
+pub enum Aperture {
+    #[default]
+    Circle,
+    Polygon {
+        blades: u32,
+        rotation: f64,
+    },
+    Mask(Arc<ApertureMask>),
+}
+
+impl Aperture {
+    pub fn sample(&self, (u, v): (f64, f64)) -> (f64, f64) {
+        match self {
+            Aperture::Circle => {
+                let point = Vec3::unit_disk_from_sample((u, v));
+                (point.x, point.y)
+            }
+            Aperture::Polygon { blades, rotation } => {
+                // Picks one of the equal-area triangles fanning out from the center, then samples
+                // it uniformly.
+                let blades = *blades as f64;
+                let wedge = (u * blades).floor().min(blades - 1.0);
+                let u = u * blades - wedge;
+                let angle = |index: f64| rotation + 2.0 * PI * index / blades;
+                let (a, b) = (angle(wedge), angle(wedge + 1.0));
+                let r = u.sqrt();
+                (
+                    r * ((1.0 - v) * a.cos() + v * b.cos()),
+                    r * ((1.0 - v) * a.sin() + v * b.sin()),
+                )
+            }
+            Aperture::Mask(mask) => mask.sample((u, v)),
+        }
+    }
+
+    pub fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        match self {
+            Aperture::Circle => fingerprint.write_str("circle"),
+            Aperture::Polygon { blades, rotation } => {
+                fingerprint.write_str("polygon");
+                fingerprint.write_f64(*blades as f64);
+                fingerprint.write_f64(*rotation);
+            }
+            Aperture::Mask(mask) => {
+                fingerprint.write_str("mask");
+                fingerprint.write_f64(mask.width as f64);
+                fingerprint.write_f64(mask.height as f64);
+                for value in &mask.conditional {
+                    fingerprint.write_f64(*value);
+                }
+            }
+        }
+    }
+}
+
+// Grayscale aperture image sampled in proportion to its brightness, through a marginal
+// distribution over rows and a conditional one over the columns of each row.
+pub struct ApertureMask {
+    width: usize,
+    height: usize,
+    marginal: Vec<f64>,
+    conditional: Vec<f64>,
+}
+
+impl ApertureMask {
+    pub fn new(image: &Image) -> io::Result<Self> {
+        let (width, height) = (image.width, image.height);
+        let mut marginal = Vec::with_capacity(height);
+        let mut conditional = Vec::with_capacity(width * height);
+        let mut total = 0.0;
+        for y in 0..height {
+            let mut row = 0.0;
+            for x in 0..width {
+                row += image.get(x, y).luminance().max(0.0);
+                conditional.push(row);
+            }
+            for value in &mut conditional[y * width..] {
+                *value = if row > 0.0 { *value / row } else { 0.0 };
+            }
+            total += row;
+            marginal.push(total);
+        }
+        if total <= 0.0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "aperture image is entirely black",
+            ));
+        }
+        for value in &mut marginal {
+            *value /= total;
+        }
+        Ok(Self {
+            width,
+            height,
+            marginal,
+            conditional,
+        })
+    }
+
+    // The longer image side spans the unit disk's diameter, with the image's top row at +y.
+    fn sample(&self, (u, v): (f64, f64)) -> (f64, f64) {
+        let (y, v_offset) = Self::sample_cdf(&self.marginal, v);
+        let row = &self.conditional[y * self.width..(y + 1) * self.width];
+        let (x, u_offset) = Self::sample_cdf(row, u);
+        let scale = 2.0 / self.width.max(self.height) as f64;
+        (
+            (x as f64 + u_offset - self.width as f64 / 2.0) * scale,
+            (self.height as f64 / 2.0 - y as f64 - v_offset) * scale,
+        )
+    }
+
+    // Returns the selected bin and the sample's relative position inside it.
+    fn sample_cdf(cdf: &[f64], sample: f64) -> (usize, f64) {
+        let index = cdf
+            .partition_point(|value| *value <= sample)
+            .min(cdf.len() - 1);
+        let low = if index == 0 { 0.0 } else { cdf[index - 1] };
+        let width = cdf[index] - low;
+        let offset = if width > 0.0 {
+            (sample - low) / width
+        } else {
+            0.5
+        };
+        (index, offset.clamp(0.0, 1.0))
+    }
+}
@@ -1,113 +1,645 @@
-use std::sync::Arc;
+use std::{f64::consts::PI, hash::Hasher, sync::Arc, time::Instant};
 
 use crate::{
+    adaptive::AdaptiveSampling,
+    aov::Aovs,
+    aperture::Aperture,
     color::Color,
+    film::Film,
+    filter::Filter,
+    fingerprint::Fingerprint,
     hittable::Hittable,
+    image::{Image, Rect},
+    integrator::{Integrator, PathTracer},
     interval::Interval,
+    lens::Lens,
+    lens_system::LensSystem,
+    physical::PhysicalCamera,
+    progressive::{Progressive, RenderState},
+    projection::Projection,
     ray::Ray,
+    sampler::{Sampler, SamplerKind},
+    stereo::{Convergence, Eye, Stereo},
     vec3::{Point3, Vec3},
 };
 
+pub struct Render {
+    pub beauty: Image,
+    pub aovs: Option<Aovs>,
+    pub sample_counts: Vec<u32>,
+}
+
+impl Render {
+    pub fn crop(&self, rect: &Rect) -> Render {
+        let width = self.beauty.width;
+        Render {
+            beauty: self.beauty.crop(rect),
+            aovs: self.aovs.as_ref().map(|aovs| aovs.crop(rect)),
+            sample_counts: (rect.y0..rect.y1)
+                .flat_map(|y| &self.sample_counts[y * width + rect.x0..y * width + rect.x1])
+                .copied()
+                .collect(),
+        }
+    }
+}
+
+struct Pass {
+    bounds: Rect,
+    samples: u32,
+    target_samples: u32,
+    deadline: Option<Instant>,
+}
+
+#[derive(Clone)]
 pub struct Camera {
     image_width: i32,
     image_height: i32,
     samples_per_pixel: i32,
     max_depth: i32,
-    pixel_samples_scale: f64,
     center: Point3,
     pixel00_loc: Point3,
     pixel_delta_u: Vec3,
     pixel_delta_v: Vec3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    viewport_height: f64,
+    focus_distance: f64,
+    defocus_radius: f64,
+    aperture: Aperture,
+    lens: Lens,
+    lens_system: Option<Arc<LensSystem>>,
+    shutter: f64,
+    readout: f64,
+    projection: Projection,
+    stereo: Option<Stereo>,
+    integrator: Arc<dyn Integrator>,
+    aovs: bool,
+    adaptive: Option<AdaptiveSampling>,
+    sampler: SamplerKind,
+    seed: u64,
+    filter: Filter,
+    crop: Option<Rect>,
 }
 
 impl Camera {
     pub fn new(aspect_ratio: f64, image_width: i32, samples_per_pixel: i32) -> Self {
         let max_depth = 50;
-        let pixel_samples_scale = 1.0 / samples_per_pixel as f64;
         let image_height = (image_width as f64 / aspect_ratio) as i32;
         let image_height = if image_height < 1 { 1 } else { image_height };
 
-        let focal_length = 1.0;
-        let viewport_height = 2.0;
-        let viewport_width = viewport_height * (image_width as f64 / image_height as f64);
-        let center = Point3::new(0.0, 0.0, 0.0);
-        let focal_point = Point3::new(0.0, 0.0, focal_length);
+        // Camera frame basis: u points right, v up and w opposite the view direction.
+        let mut camera = Self {
+            image_width,
+            image_height,
+            samples_per_pixel,
+            max_depth,
+            center: Point3::new(0.0, 0.0, 0.0),
+            pixel00_loc: Point3::default(),
+            pixel_delta_u: Vec3::default(),
+            pixel_delta_v: Vec3::default(),
+            u: Vec3::new(1.0, 0.0, 0.0),
+            v: Vec3::new(0.0, 1.0, 0.0),
+            w: Vec3::new(0.0, 0.0, 1.0),
+            viewport_height: 2.0,
+            focus_distance: 1.0,
+            defocus_radius: 0.0,
+            aperture: Aperture::default(),
+            lens: Lens::default(),
+            lens_system: None,
+            shutter: 1.0,
+            readout: 0.0,
+            projection: Projection::default(),
+            stereo: None,
+            integrator: Arc::new(PathTracer),
+            aovs: false,
+            adaptive: None,
+            sampler: SamplerKind::default(),
+            seed: 0,
+            filter: Filter::default(),
+            crop: None,
+        };
+        camera.set_viewport();
+        camera
+    }
+
+    // Places the viewport at the focus distance, spanning `viewport_height` there.
+    fn set_viewport(&mut self) {
+        let (width, height) = (self.image_width as f64, self.image_height as f64);
+        let viewport_width = self.viewport_height * (width / height);
 
-        let viewport_u = Vec3::new(viewport_width, 0.0, 0.0);
-        let viewport_v = Vec3::new(0.0, -viewport_height, 0.0);
+        let viewport_u = &self.u * viewport_width;
+        let viewport_v = &self.v * -self.viewport_height;
 
-        let pixel_delta_u = &viewport_u / image_width as f64;
-        let pixel_delta_v = &viewport_v / image_height as f64;
+        self.pixel_delta_u = &viewport_u / width;
+        self.pixel_delta_v = &viewport_v / height;
 
-        let mut viewport_upper_left = &center - &focal_point;
+        let mut viewport_upper_left = &self.center - &(&self.w * self.focus_distance);
         viewport_upper_left -= &viewport_u / 2.0;
         viewport_upper_left -= &viewport_v / 2.0;
 
-        let pixel00_loc = &pixel_delta_u * 0.5 + &pixel_delta_v * 0.5 + &viewport_upper_left;
-        Self {
-            image_width,
-            image_height,
-            samples_per_pixel,
-            max_depth,
-            pixel_samples_scale,
-            center,
-            pixel00_loc,
-            pixel_delta_u,
-            pixel_delta_v,
+        self.pixel00_loc =
+            &self.pixel_delta_u * 0.5 + &self.pixel_delta_v * 0.5 + &viewport_upper_left;
+    }
+
+    pub fn with_physical(mut self, physical: Option<PhysicalCamera>) -> Self {
+        if let Some(physical) = physical {
+            self.focus_distance = physical.focus_distance;
+            self.defocus_radius = physical.aperture_radius();
+            self.shutter = physical.shutter;
+            let aspect_ratio = self.image_width as f64 / self.image_height as f64;
+            self.viewport_height = physical.viewport_height(aspect_ratio);
+            self.set_viewport();
         }
+        self
     }
 
-    pub fn render(&self, world: Arc<dyn Hittable>) {
-        let pb = indicatif::ProgressBar::new((self.image_width * self.image_height) as u64);
+    pub fn with_view(mut self, lookfrom: Point3, lookat: Point3, vup: Vec3) -> Self {
+        self.w = (&lookfrom - &lookat).unit();
+        self.u = vup.cross(&self.w).unit();
+        self.v = self.w.cross(&self.u);
+        self.center = lookfrom;
+        self.set_viewport();
+        self
+    }
 
-        println!("P3\n{} {}\n255", self.image_width, self.image_height);
+    pub fn with_vfov(mut self, vfov: f64) -> Self {
+        self.viewport_height = 2.0 * (vfov.to_radians() / 2.0).tan() * self.focus_distance;
+        self.set_viewport();
+        self
+    }
 
-        for j in 0..self.image_height {
-            for i in 0..self.image_width {
-                let mut pixel_color = Color::new(0.0, 0.0, 0.0);
-                for _ in 0..self.samples_per_pixel {
-                    let ray = self.get_ray(i, j);
-                    pixel_color += Camera::ray_color(&ray, world.clone(), self.max_depth);
-                }
-                let pixel_color = pixel_color * self.pixel_samples_scale;
-                print!("{pixel_color}");
-                pb.inc(1);
+    pub fn with_shutter(mut self, shutter: f64) -> Self {
+        self.shutter = shutter;
+        self
+    }
+
+    pub fn with_aperture(mut self, aperture: Aperture) -> Self {
+        self.aperture = aperture;
+        self
+    }
+
+    pub fn with_lens(mut self, lens: Lens) -> Self {
+        self.lens = lens;
+        self
+    }
+
+    pub fn with_lens_system(mut self, lens_system: Option<LensSystem>) -> Self {
+        self.lens_system = lens_system.map(Arc::new);
+        self
+    }
+
+    // Exposes the rows one after another like a CMOS sensor, the last one starting `readout`
+    // after the first.
+    pub fn with_rolling_shutter(mut self, readout: Option<f64>) -> Self {
+        self.readout = readout.unwrap_or(0.0);
+        self
+    }
+
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    pub fn with_stereo(mut self, stereo: Option<Stereo>) -> Self {
+        self.stereo = stereo;
+        self
+    }
+
+    pub fn with_integrator(mut self, integrator: Arc<dyn Integrator>) -> Self {
+        self.integrator = integrator;
+        self
+    }
+
+    pub fn with_aovs(mut self, aovs: bool) -> Self {
+        self.aovs = aovs;
+        self
+    }
+
+    pub fn with_adaptive_sampling(mut self, adaptive: Option<AdaptiveSampling>) -> Self {
+        self.adaptive = adaptive;
+        self
+    }
+
+    pub fn with_sampler(mut self, sampler: SamplerKind, seed: u64) -> Self {
+        self.sampler = sampler;
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_crop(mut self, crop: Option<Rect>) -> Self {
+        self.crop = crop;
+        self
+    }
+
+    pub fn crop(&self) -> Option<Rect> {
+        let (width, height) = self.film_size();
+        self.crop.map(|crop| crop.clamp(width, height))
+    }
+
+    pub fn film_size(&self) -> (usize, usize) {
+        let size = (self.image_width as usize, self.image_height as usize);
+        match &self.stereo {
+            Some(stereo) => stereo.film_size(size),
+            None => size,
+        }
+    }
+
+    pub fn new_state(&self, world: &dyn Hittable) -> RenderState {
+        let (width, height) = self.film_size();
+        RenderState::new(
+            Film::new(width, height, self.filter),
+            self.aovs,
+            self.sampler,
+            self.max_samples() as u32,
+            vec![self.seed],
+            self.scene_hash(world),
+        )
+    }
+
+    pub fn scene_hash(&self, world: &dyn Hittable) -> u64 {
+        let mut fingerprint = Fingerprint::default();
+        fingerprint.write_vec3(&self.center);
+        fingerprint.write_vec3(&self.pixel00_loc);
+        fingerprint.write_vec3(&self.pixel_delta_u);
+        fingerprint.write_vec3(&self.pixel_delta_v);
+        fingerprint.write_f64(self.defocus_radius);
+        if self.defocus_radius > 0.0 {
+            self.aperture.fingerprint(&mut fingerprint);
+        }
+        fingerprint.write_f64(self.shutter);
+        if self.readout > 0.0 {
+            fingerprint.write_f64(self.readout);
+        }
+        self.lens.fingerprint(&mut fingerprint);
+        if let Some(lens_system) = &self.lens_system {
+            lens_system.fingerprint(&mut fingerprint);
+        }
+        self.projection.fingerprint(&mut fingerprint);
+        if let Some(stereo) = &self.stereo {
+            stereo.fingerprint(&mut fingerprint);
+        }
+        fingerprint.write_i32(self.max_depth);
+        self.integrator.fingerprint(&mut fingerprint);
+        world.fingerprint(&mut fingerprint);
+        fingerprint.finish()
+    }
+
+    pub fn render_progressive(
+        &self,
+        world: Arc<dyn Hittable>,
+        state: &mut RenderState,
+        progressive: &Progressive,
+        mut on_snapshot: impl FnMut(&RenderState),
+    ) {
+        let max_samples = self.max_samples();
+        let target_samples = match (progressive.time_budget, self.adaptive) {
+            (Some(_), None) => u32::MAX,
+            _ => max_samples as u32,
+        };
+        // Time budgets and snapshots are only checked between passes, so they default to
+        // single-sample passes.
+        let pass_samples = match progressive.pass_samples {
+            Some(samples) => samples.max(1) as u32,
+            None if progressive.time_budget.is_some()
+                || progressive.snapshot_interval.is_some() =>
+            {
+                1
+            }
+            None => target_samples,
+        };
+        let mut sampler = self.sampler.build(max_samples, self.seed);
+
+        let start = Instant::now();
+        let deadline = progressive.time_budget.map(|budget| start + budget);
+        let pb = match progressive.time_budget {
+            Some(budget) => indicatif::ProgressBar::new(budget.as_secs().max(1)),
+            None => {
+                let passes = target_samples.div_ceil(pass_samples) as u64;
+                indicatif::ProgressBar::new(self.sample_bounds().area() as u64 * passes)
+            }
+        };
+
+        let pass = Pass {
+            bounds: self.sample_bounds(),
+            samples: pass_samples,
+            target_samples,
+            deadline,
+        };
+        let mut last_snapshot = start;
+        loop {
+            let rendered = self.render_pass(world.as_ref(), state, sampler.as_mut(), &pass, &pb);
+            if rendered {
+                state.passes += 1;
+            }
+            if let Some(budget) = progressive.time_budget {
+                pb.set_position(start.elapsed().min(budget).as_secs());
+            }
+
+            let out_of_time = deadline.is_some_and(|deadline| Instant::now() >= deadline);
+            let finished = !rendered || out_of_time;
+            let snapshot_due = progressive
+                .snapshot_interval
+                .is_none_or(|interval| last_snapshot.elapsed() >= interval);
+            if rendered && !finished && snapshot_due {
+                on_snapshot(state);
+                last_snapshot = Instant::now();
+            }
+            if finished {
+                break;
             }
         }
         pb.finish_with_message("done");
     }
 
-    fn get_ray(&self, i: i32, j: i32) -> Ray {
-        let offset = Camera::sample_square();
-        let pixel_sample = &self.pixel_delta_u * (i as f64 + offset.x)
-            + &self.pixel_delta_v * (j as f64 + offset.y)
-            + &self.pixel00_loc;
+    pub fn render_tile(&self, world: &dyn Hittable, state: &mut RenderState, tile: &Rect) {
+        let max_samples = self.max_samples();
+        let mut sampler = self.sampler.build(max_samples, self.seed);
+        let pass = Pass {
+            bounds: *tile,
+            samples: max_samples as u32,
+            target_samples: max_samples as u32,
+            deadline: None,
+        };
+        self.render_pass(
+            world,
+            state,
+            sampler.as_mut(),
+            &pass,
+            &indicatif::ProgressBar::hidden(),
+        );
+    }
 
-        let ray_direction = &pixel_sample - &self.center;
-        Ray::new(self.center.clone(), ray_direction)
+    fn splat_bounds(&self, position: (f64, f64)) -> Rect {
+        let size = (self.image_width as usize, self.image_height as usize);
+        match &self.stereo {
+            Some(stereo) => {
+                let (width, height) = (size.0 as f64, size.1 as f64);
+                let (eye, _) = stereo.eye(position, (width, height));
+                stereo.eye_bounds(eye, size)
+            }
+            None => {
+                let (width, height) = self.film_size();
+                Rect::new(0, 0, width, height)
+            }
+        }
     }
 
-    fn sample_square() -> Vec3 {
-        Vec3::new(
-            rand::random::<f64>() - 0.5,
-            rand::random::<f64>() - 0.5,
-            0.0,
-        )
+    fn max_samples(&self) -> i32 {
+        self.adaptive
+            .map_or(self.samples_per_pixel, |adaptive| adaptive.max_samples)
     }
 
-    fn ray_color(ray: &Ray, world: Arc<dyn Hittable>, depth: i32) -> Color {
-        if depth <= 0 {
-            return Color::new(0.0, 0.0, 0.0);
+    fn render_pass(
+        &self,
+        world: &dyn Hittable,
+        state: &mut RenderState,
+        sampler: &mut dyn Sampler,
+        pass: &Pass,
+        pb: &indicatif::ProgressBar,
+    ) -> bool {
+        let (width, _) = self.film_size();
+        let bounds = pass.bounds;
+        let mut rendered = false;
+        for j in bounds.y0..bounds.y1 {
+            if pass
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+            {
+                break;
+            }
+            for i in bounds.x0..bounds.x1 {
+                let index = j * width + i;
+                let (i, j) = (i as i32, j as i32);
+                let stats = &mut state.stats[index];
+                let first = stats.count;
+                let last = first.saturating_add(pass.samples).min(pass.target_samples);
+                for sample_index in first..last {
+                    if self
+                        .adaptive
+                        .is_some_and(|adaptive| adaptive.converged(stats))
+                    {
+                        break;
+                    }
+                    sampler.start_pixel_sample((i, j), sample_index);
+                    let (offset_x, offset_y) = sampler.get_2d();
+                    let film_position = (i as f64 + offset_x, j as f64 + offset_y);
+                    let sample = match self.get_ray(film_position, sampler) {
+                        Some((ray, weight)) => {
+                            if let Some(aov_pixels) = state.aov_pixels.as_mut() {
+                                let hit = world.hit(&ray, &Interval::new(0.001, f64::INFINITY));
+                                aov_pixels[index].add(&ray, hit.as_ref());
+                            }
+                            self.integrator
+                                .ray_color(&ray, world, self.max_depth, sampler)
+                                * weight
+                        }
+                        None => Color::default(),
+                    };
+                    stats.add(&sample);
+                    state.film.add_sample(
+                        film_position,
+                        &sample,
+                        &self.splat_bounds(film_position),
+                    );
+                    rendered = true;
+                }
+                if pass.deadline.is_none() {
+                    pb.inc(1);
+                }
+            }
         }
-        if let Some(hit) = world.hit(ray, &Interval::new(0.001, f64::INFINITY)) {
-            return if let Some(scatter) = hit.material.scatter(ray, &hit) {
-                scatter.attenuation * Camera::ray_color(&scatter.ray, world, depth - 1)
-            } else {
-                Color::new(0.0, 0.0, 0.0)
-            };
+        rendered
+    }
+
+    // Samples just outside the crop window still splat into its edge pixels, so widen the
+    // sampled area by the filter footprint.
+    pub fn sample_bounds(&self) -> Rect {
+        let (width, height) = self.film_size();
+        match self.crop() {
+            Some(crop) => crop.expand(self.filter.margin()).clamp(width, height),
+            None => Rect::new(0, 0, width, height),
+        }
+    }
+
+    // Returns no ray for film positions the projection does not cover, such as the corners
+    // outside a circular fisheye image. The ray comes with a weight for its radiance, which
+    // isolates a single color channel when chromatic aberration bends each one differently.
+    fn get_ray(&self, (x, y): (f64, f64), sampler: &mut dyn Sampler) -> Option<(Ray, Color)> {
+        let shutter_sample = sampler.get_1d();
+        let (channel, weight) = if self.lens.chromatic_aberration != 0.0 {
+            let channel = ((sampler.get_1d() * 3.0) as usize).min(2);
+            let mut weight = [0.0; 3];
+            weight[channel] = 3.0;
+            (Some(channel), Color::new(weight[0], weight[1], weight[2]))
+        } else {
+            (None, Color::new(1.0, 1.0, 1.0))
+        };
+        let (width, height) = (self.image_width as f64, self.image_height as f64);
+        let (eye, (x, y)) = match &self.stereo {
+            Some(stereo) => {
+                let (eye, position) = stereo.eye((x, y), (width, height));
+                (Some(eye), position)
+            }
+            None => (None, (x, y)),
+        };
+        let (s, t) = (x / width, y / height);
+        let time = (y.floor() / height) * self.readout + shutter_sample * self.shutter;
+        if let Some(lens_system) = &self.lens_system {
+            let (ray, falloff) = lens_system.ray((s, t), sampler.get_2d())?;
+            let origin = self.to_world(ray.origin.x, ray.origin.y, ray.origin.z) + &self.center;
+            let direction = self.to_world(ray.direction.x, ray.direction.y, ray.direction.z);
+            return Some((Ray::with_time(origin, direction, time), weight * falloff));
+        }
+        let (origin, direction) = match self.projection {
+            Projection::Perspective => {
+                let (shift_x, shift_y) = self.lens.shift;
+                let pixel_sample = &self.pixel_delta_u * (x - 0.5 + shift_x * width)
+                    + &self.pixel_delta_v * (y - 0.5 - shift_y * height)
+                    + &self.pixel00_loc;
+                let mut direction = &pixel_sample - &self.center;
+                if self.lens.distorts() {
+                    let depth = self.depth(&direction);
+                    let image = (
+                        direction.dot(&self.u) / depth,
+                        direction.dot(&self.v) / depth,
+                    );
+                    let (x, y) = self.lens.undistort(image, channel);
+                    direction = self.to_world(x * depth, y * depth, depth);
+                }
+                (self.center.clone(), direction)
+            }
+            Projection::Orthographic { view_width } => {
+                let view_height = view_width * height / width;
+                let origin = &self.u * ((s - 0.5) * view_width)
+                    + &self.v * ((0.5 - t) * view_height)
+                    + &self.center;
+                (origin, -self.w.clone())
+            }
+            Projection::Equirectangular => {
+                let phi = (s - 0.5) * 2.0 * PI;
+                let theta = t * PI;
+                let direction = self.to_world(
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                    theta.sin() * phi.cos(),
+                );
+                (self.center.clone(), direction)
+            }
+            Projection::Fisheye { fov, circular } => {
+                // Equidistant fisheye: the angle from the view axis grows linearly with the
+                // distance from the image center. The circular image fits the shorter side, the
+                // full-frame one reaches the corners.
+                let scale = width.min(height) / 2.0;
+                let (px, py) = ((x - width / 2.0) / scale, (height / 2.0 - y) / scale);
+                let radius = if circular {
+                    1.0
+                } else {
+                    width.hypot(height) / 2.0 / scale
+                };
+                let r = px.hypot(py) / radius;
+                if r > 1.0 {
+                    return None;
+                }
+                let theta = r * fov.to_radians() / 2.0;
+                let phi = py.atan2(px);
+                let direction = self.to_world(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                );
+                (self.center.clone(), direction)
+            }
+        };
+        let (origin, direction) = match (eye, &self.stereo) {
+            (Some(eye), Some(stereo)) => self.eye_ray(eye, stereo, origin, direction),
+            _ => (origin, direction),
+        };
+        if self.defocus_radius <= 0.0 {
+            return Some((Ray::with_time(origin, direction, time), weight));
         }
-        let unit_direction = ray.direction.clone().unit();
-        let a = 0.5 * unit_direction.y + 1.0;
-        Color::new(1.0, 1.0, 1.0) * (1.0 - a) + Color::new(0.5, 0.7, 1.0) * a
+        // Thin lens: every ray through the aperture meets the pinhole ray on the plane of focus.
+        let focus_point = &(&direction * self.focus_distance(&origin, &direction)) + &origin;
+        let (lens_x, lens_y) = self.aperture.sample(sampler.get_2d());
+        let origin = &self.u * (lens_x * self.defocus_radius)
+            + &self.v * (lens_y * self.defocus_radius)
+            + &origin;
+        let direction = &focus_point - &origin;
+        Some((Ray::with_time(origin, direction, time), weight))
+    }
+
+    // Ray parameter at which a pinhole ray reaches the plane of focus, which a tilted lens
+    // rotates about the horizontal axis through the focus distance.
+    fn focus_distance(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        let untilted = self.focus_distance / self.depth(direction);
+        if self.lens.tilt == 0.0 || self.projection != Projection::Perspective {
+            return untilted;
+        }
+        let tilt = self.lens.tilt.to_radians();
+        let normal = &self.w * tilt.cos() + &self.v * tilt.sin();
+        let plane_point = &self.center - &(&self.w * self.focus_distance);
+        let t = (&plane_point - origin).dot(&normal) / direction.dot(&normal);
+        if t.is_finite() && t > 0.0 {
+            t
+        } else {
+            untilted
+        }
+    }
+
+    // Moves a ray of the central camera to one eye. Omni-directional stereo keeps the baseline
+    // perpendicular to each ray's azimuth, so every viewing direction of the panorama has
+    // parallax, and converges each ray individually since a whole sphere cannot be toed in.
+    fn eye_ray(
+        &self,
+        eye: Eye,
+        stereo: &Stereo,
+        origin: Point3,
+        direction: Vec3,
+    ) -> (Point3, Vec3) {
+        let offset = eye.sign() * stereo.interocular / 2.0;
+        let distance = stereo.convergence_distance;
+        let panoramic = self.projection == Projection::Equirectangular;
+        let baseline = if panoramic {
+            direction.unit().cross(&self.v)
+        } else {
+            self.u.clone()
+        };
+        let eye_origin = &baseline * offset + &origin;
+        let direction = match stereo.convergence {
+            Convergence::ToeIn if !panoramic => {
+                // Rotates the eye about the up axis so both view axes meet at the convergence
+                // distance.
+                let angle = (offset / distance).atan();
+                let axis = &self.v;
+                &direction * angle.cos()
+                    + &axis.cross(&direction) * angle.sin()
+                    + axis * (axis.dot(&direction) * (1.0 - angle.cos()))
+            }
+            _ => {
+                // Aims through the point the central ray reaches on the zero-parallax plane (or
+                // sphere, for panoramic projections), which both eyes share.
+                &(&direction * (distance / self.depth(&direction)) + &origin) - &eye_origin
+            }
+        };
+        (eye_origin, direction)
+    }
+
+    // Distance covered per unit of ray parameter towards the focus or convergence surface: a
+    // plane for planar projections, a sphere around the camera for panoramic ones.
+    fn depth(&self, direction: &Vec3) -> f64 {
+        match self.projection {
+            Projection::Perspective | Projection::Orthographic { .. } => -direction.dot(&self.w),
+            Projection::Equirectangular | Projection::Fisheye { .. } => direction.len(),
+        }
+    }
+
+    // Converts camera-space coordinates along the right, up and view directions to world space.
+    fn to_world(&self, right: f64, up: f64, forward: f64) -> Vec3 {
+        &self.u * right + &self.v * up - &self.w * forward
     }
 }
@@ -0,0 +1,215 @@
+use std::io::{self, BufRead, Read, Write};
+
+use crate::{
+    adaptive::PixelStats,
+    aov::AovPixel,
+    color::Color,
+    film::{Film, FilmPixel},
+    filter::{Filter, FilterKind},
+    progressive::RenderState,
+    sampler::SamplerKind,
+    vec3::Vec3,
+};
+
+const MAGIC: &str = "RTCHECKPOINT 4";
+
+pub fn save(out: &mut impl Write, state: &RenderState) -> io::Result<()> {
+    let film = &state.film;
+    let seeds: Vec<String> = state.seeds.iter().map(u64::to_string).collect();
+    let spp = state
+        .stats
+        .iter()
+        .map(|stats| stats.count)
+        .max()
+        .unwrap_or(0);
+    writeln!(out, "{MAGIC}")?;
+    writeln!(out, "scene {:016x}", state.scene_hash)?;
+    writeln!(out, "resolution {} {}", film.width, film.height)?;
+    writeln!(out, "spp {spp}")?;
+    writeln!(
+        out,
+        "sampler {} {}",
+        state.sampler.name(),
+        state.sampler_spp
+    )?;
+    writeln!(out, "seeds {}", seeds.join(","))?;
+    writeln!(
+        out,
+        "filter {} {}",
+        film.filter.kind.name(),
+        film.filter.radius
+    )?;
+    writeln!(out, "passes {}", state.passes)?;
+    writeln!(out, "aovs {}", state.aov_pixels.is_some() as u8)?;
+
+    for (pixel, stats) in film.pixels.iter().zip(&state.stats) {
+        write_film_pixel(out, pixel)?;
+        write_stats(out, stats)?;
+    }
+
+    if let Some(aov_pixels) = &state.aov_pixels {
+        for pixel in aov_pixels {
+            write_aov_pixel(out, pixel)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn load(input: &mut impl BufRead) -> io::Result<RenderState> {
+    let magic = read_line(input)?;
+    if magic != MAGIC {
+        return Err(invalid(format!("not a checkpoint file (found '{magic}')")));
+    }
+    let [scene_hash] = read_field(input, "scene")?;
+    let [width, height] = read_field(input, "resolution")?;
+    let [_spp] = read_field(input, "spp")?;
+    let [sampler, sampler_spp] = read_field(input, "sampler")?;
+    let [seeds] = read_field(input, "seeds")?;
+    let [filter, radius] = read_field(input, "filter")?;
+    let [passes] = read_field(input, "passes")?;
+    let [aovs] = read_field(input, "aovs")?;
+
+    let scene_hash = u64::from_str_radix(&scene_hash, 16)
+        .map_err(|_| invalid(format!("invalid scene hash '{scene_hash}'")))?;
+    let seeds = seeds.split(',').map(parse).collect::<io::Result<_>>()?;
+    let sampler: SamplerKind = sampler.parse().map_err(invalid)?;
+    let filter: FilterKind = filter.parse().map_err(invalid)?;
+    let radius: f64 = parse(&radius)?;
+    if !(radius.is_finite() && radius >= 0.5) {
+        return Err(invalid(format!("invalid filter radius '{radius}'")));
+    }
+    let filter = Filter::new(filter, Some(radius));
+
+    let mut state = RenderState::new(
+        Film::new(parse(&width)?, parse(&height)?, filter),
+        parse::<u8>(&aovs)? != 0,
+        sampler,
+        parse(&sampler_spp)?,
+        seeds,
+        scene_hash,
+    );
+    state.passes = parse(&passes)?;
+
+    for (pixel, stats) in state.film.pixels.iter_mut().zip(&mut state.stats) {
+        *pixel = read_film_pixel(input)?;
+        *stats = read_stats(input)?;
+    }
+
+    if let Some(aov_pixels) = state.aov_pixels.as_mut() {
+        for pixel in aov_pixels {
+            *pixel = read_aov_pixel(input)?;
+        }
+    }
+    Ok(state)
+}
+
+pub fn write_film_pixel(out: &mut impl Write, pixel: &FilmPixel) -> io::Result<()> {
+    write_vec3(out, &pixel.sum)?;
+    write_f64(out, pixel.weight)
+}
+
+pub fn read_film_pixel(input: &mut impl Read) -> io::Result<FilmPixel> {
+    Ok(FilmPixel {
+        sum: read_vec3(input)?,
+        weight: read_f64(input)?,
+    })
+}
+
+pub fn write_stats(out: &mut impl Write, stats: &PixelStats) -> io::Result<()> {
+    write_u64(out, stats.count as u64)?;
+    write_f64(out, stats.mean)?;
+    write_f64(out, stats.m2)
+}
+
+pub fn read_stats(input: &mut impl Read) -> io::Result<PixelStats> {
+    Ok(PixelStats {
+        count: read_u64(input)? as u32,
+        mean: read_f64(input)?,
+        m2: read_f64(input)?,
+    })
+}
+
+pub fn write_aov_pixel(out: &mut impl Write, pixel: &AovPixel) -> io::Result<()> {
+    write_vec3(out, &pixel.albedo)?;
+    write_vec3(out, &pixel.normal)?;
+    write_f64(out, pixel.depth)?;
+    write_u64(out, pixel.object_id.map_or(u64::MAX, |id| id as u64))?;
+    write_u64(out, pixel.material.map_or(u64::MAX, |id| id as u64))?;
+    write_u64(out, pixel.hits as u64)?;
+    write_u64(out, pixel.samples as u64)
+}
+
+pub fn read_aov_pixel(input: &mut impl Read) -> io::Result<AovPixel> {
+    let id = |value: u64| (value != u64::MAX).then_some(value as usize);
+    Ok(AovPixel {
+        albedo: Color(read_vec3(input)?),
+        normal: read_vec3(input)?,
+        depth: read_f64(input)?,
+        object_id: id(read_u64(input)?),
+        material: id(read_u64(input)?),
+        hits: read_u64(input)? as u32,
+        samples: read_u64(input)? as u32,
+    })
+}
+
+pub fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+pub fn parse<T: std::str::FromStr>(value: &str) -> io::Result<T> {
+    value
+        .parse()
+        .map_err(|_| invalid(format!("invalid field '{value}'")))
+}
+
+pub fn read_line(input: &mut impl BufRead) -> io::Result<String> {
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    Ok(line.trim_end().to_string())
+}
+
+pub fn read_field<const N: usize>(input: &mut impl BufRead, key: &str) -> io::Result<[String; N]> {
+    let line = read_line(input)?;
+    let mut fields = line.split_whitespace().map(str::to_string);
+    if fields.next().as_deref() != Some(key) {
+        return Err(invalid(format!("expected '{key}' header, found '{line}'")));
+    }
+    fields
+        .collect::<Vec<_>>()
+        .try_into()
+        .map_err(|_| invalid(format!("malformed header line '{line}'")))
+}
+
+fn write_f64(out: &mut impl Write, value: f64) -> io::Result<()> {
+    out.write_all(&value.to_le_bytes())
+}
+
+fn write_u64(out: &mut impl Write, value: u64) -> io::Result<()> {
+    out.write_all(&value.to_le_bytes())
+}
+
+fn write_vec3(out: &mut impl Write, value: &Vec3) -> io::Result<()> {
+    write_f64(out, value.x)?;
+    write_f64(out, value.y)?;
+    write_f64(out, value.z)
+}
+
+fn read_f64(input: &mut impl Read) -> io::Result<f64> {
+    let mut bytes = [0; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(f64::from_le_bytes(bytes))
+}
+
+fn read_u64(input: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_vec3(input: &mut impl Read) -> io::Result<Vec3> {
+    Ok(Vec3::new(
+        read_f64(input)?,
+        read_f64(input)?,
+        read_f64(input)?,
+    ))
+}
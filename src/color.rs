@@ -2,7 +2,7 @@ use std::ops::Deref;
 
 use crate::{interval::Interval, vec3::Vec3};
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Color(pub Vec3);
 
 impl Color {
@@ -10,19 +10,49 @@ impl Color {
         Self(Vec3::new(r, g, b))
     }
 
-    pub fn to_gamma_space(&self) -> Self {
+    pub fn to_srgb_space(&self) -> Self {
         Self(Vec3::new(
-            Self::linear_to_gamma(self.x),
-            Self::linear_to_gamma(self.y),
-            Self::linear_to_gamma(self.z),
+            Self::linear_to_srgb(self.x),
+            Self::linear_to_srgb(self.y),
+            Self::linear_to_srgb(self.z),
         ))
     }
 
-    fn linear_to_gamma(component: f64) -> f64 {
-        if component > 0.0 {
-            component.sqrt()
-        } else {
+    pub fn to_linear_space(&self) -> Self {
+        Self(Vec3::new(
+            Self::srgb_to_linear(self.x),
+            Self::srgb_to_linear(self.y),
+            Self::srgb_to_linear(self.z),
+        ))
+    }
+
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.x + 0.7152 * self.y + 0.0722 * self.z
+    }
+
+    pub fn heatmap(t: f64) -> Self {
+        let t = Interval::new(0.0, 1.0).clamp(t);
+        let r = Interval::new(0.0, 1.0).clamp(t * 3.0);
+        let g = Interval::new(0.0, 1.0).clamp(t * 3.0 - 1.0);
+        let b = Interval::new(0.0, 1.0).clamp(t * 3.0 - 2.0);
+        Self::new(r, g, b)
+    }
+
+    fn linear_to_srgb(component: f64) -> f64 {
+        if component <= 0.0 {
             0.0
+        } else if component <= 0.0031308 {
+            12.92 * component
+        } else {
+            1.055 * component.powf(1.0 / 2.4) - 0.055
+        }
+    }
+
+    fn srgb_to_linear(component: f64) -> f64 {
+        if component <= 0.04045 {
+            component / 12.92
+        } else {
+            ((component + 0.055) / 1.055).powf(2.4)
         }
     }
 }
@@ -34,13 +64,13 @@ impl std::fmt::Display for Color {
             max: 0.999,
         };
 
-        let color = self.to_gamma_space();
+        let color = self.to_srgb_space();
 
         let r: u8 = (256.0 * INTENSITY.clamp(color.x)) as u8;
         let g: u8 = (256.0 * INTENSITY.clamp(color.y)) as u8;
         let b: u8 = (256.0 * INTENSITY.clamp(color.z)) as u8;
 
-        write!(f, "{} {} {}\n", r, g, b)
+        writeln!(f, "{} {} {}", r, g, b)
     }
 }
 
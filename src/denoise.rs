@@ -0,0 +1,187 @@
+use crate::{aov::Aovs, color::Color, image::Image, vec3::Vec3};
+
+#[derive(Clone, Copy)]
+pub enum Denoiser {
+    Bilateral,
+    Atrous,
+}
+
+struct Guides<'a> {
+    albedo: &'a Image,
+    normal: &'a Image,
+    depth: &'a Image,
+}
+
+impl Guides<'_> {
+    const SIGMA_ALBEDO: f64 = 0.1;
+    const SIGMA_NORMAL: f64 = 0.3;
+    const SIGMA_DEPTH: f64 = 0.1;
+
+    fn weight(&self, p: (usize, usize), q: (usize, usize)) -> f64 {
+        let albedo = self.albedo.get(p.0, p.1).0.clone() - &self.albedo.get(q.0, q.1).0;
+        let normal = self.normal.get(p.0, p.1).0.clone() - &self.normal.get(q.0, q.1).0;
+        let depth_p = self.depth.get(p.0, p.1).x;
+        let depth_q = self.depth.get(q.0, q.1).x;
+        let depth = match (depth_p.is_finite(), depth_q.is_finite()) {
+            (true, true) => (depth_p - depth_q).abs() / depth_p.max(depth_q).max(1e-4),
+            (false, false) => 0.0,
+            _ => return 0.0,
+        };
+        let exponent = albedo.len_squared() / (Self::SIGMA_ALBEDO * Self::SIGMA_ALBEDO)
+            + normal.len_squared() / (Self::SIGMA_NORMAL * Self::SIGMA_NORMAL)
+            + depth * depth / (Self::SIGMA_DEPTH * Self::SIGMA_DEPTH);
+        (-exponent).exp()
+    }
+}
+
+impl Denoiser {
+    const SIGMA_COLOR: f64 = 0.5;
+
+    pub fn apply(&self, beauty: &Image, aovs: &Aovs) -> Image {
+        let guides = Guides {
+            albedo: &aovs.albedo,
+            normal: &aovs.normal,
+            depth: &aovs.depth,
+        };
+        let irradiance = Self::demodulate(beauty, &aovs.albedo);
+        let filtered = match self {
+            Denoiser::Bilateral => Self::bilateral(&irradiance, &guides),
+            Denoiser::Atrous => Self::atrous(&irradiance, &guides),
+        };
+        Self::remodulate(&filtered, &aovs.albedo)
+    }
+
+    fn demodulate(beauty: &Image, albedo: &Image) -> Image {
+        Self::map_albedo(beauty, albedo, |c, a| c / a.max(1e-3))
+    }
+
+    fn remodulate(irradiance: &Image, albedo: &Image) -> Image {
+        Self::map_albedo(irradiance, albedo, |c, a| c * a.max(1e-3))
+    }
+
+    fn map_albedo(image: &Image, albedo: &Image, f: impl Fn(f64, f64) -> f64) -> Image {
+        let mut out = image.clone();
+        for (pixel, albedo) in out.pixels.iter_mut().zip(&albedo.pixels) {
+            *pixel = Color::new(
+                f(pixel.x, albedo.x),
+                f(pixel.y, albedo.y),
+                f(pixel.z, albedo.z),
+            );
+        }
+        out
+    }
+
+    fn color_weight(p: &Color, q: &Color, sigma: f64) -> f64 {
+        let difference = p.0.clone() - &q.0;
+        (-difference.len_squared() / (sigma * sigma)).exp()
+    }
+
+    fn bilateral(image: &Image, guides: &Guides) -> Image {
+        const RADIUS: i64 = 5;
+        const SIGMA_SPATIAL: f64 = 2.5;
+
+        Self::filter(image, |x, y, out| {
+            let center = image.get(x, y);
+            for dy in -RADIUS..=RADIUS {
+                for dx in -RADIUS..=RADIUS {
+                    let Some((qx, qy)) = Self::offset(image, x, y, dx, dy) else {
+                        continue;
+                    };
+                    let spatial = (-((dx * dx + dy * dy) as f64)
+                        / (2.0 * SIGMA_SPATIAL * SIGMA_SPATIAL))
+                        .exp();
+                    let sample = image.get(qx, qy);
+                    let weight = spatial
+                        * guides.weight((x, y), (qx, qy))
+                        * Self::color_weight(center, sample, Self::SIGMA_COLOR);
+                    out.add(sample, weight);
+                }
+            }
+        })
+    }
+
+    fn atrous(image: &Image, guides: &Guides) -> Image {
+        const KERNEL: [f64; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+        const ITERATIONS: u32 = 5;
+
+        let mut current = image.clone();
+        for iteration in 0..ITERATIONS {
+            let step = 1i64 << iteration;
+            let sigma = Self::SIGMA_COLOR / (1u32 << iteration) as f64;
+            let source = current;
+            current = Self::filter(&source, |x, y, out| {
+                let center = source.get(x, y);
+                for (ky, wy) in KERNEL.iter().enumerate() {
+                    for (kx, wx) in KERNEL.iter().enumerate() {
+                        let dx = (kx as i64 - 2) * step;
+                        let dy = (ky as i64 - 2) * step;
+                        let Some((qx, qy)) = Self::offset(&source, x, y, dx, dy) else {
+                            continue;
+                        };
+                        let sample = source.get(qx, qy);
+                        let weight = wx
+                            * wy
+                            * guides.weight((x, y), (qx, qy))
+                            * Self::color_weight(center, sample, sigma.max(1e-3));
+                        out.add(sample, weight);
+                    }
+                }
+            });
+        }
+        current
+    }
+
+    fn offset(image: &Image, x: usize, y: usize, dx: i64, dy: i64) -> Option<(usize, usize)> {
+        let qx = x as i64 + dx;
+        let qy = y as i64 + dy;
+        if qx < 0 || qy < 0 || qx >= image.width as i64 || qy >= image.height as i64 {
+            None
+        } else {
+            Some((qx as usize, qy as usize))
+        }
+    }
+
+    fn filter(image: &Image, kernel: impl Fn(usize, usize, &mut WeightedSum)) -> Image {
+        let mut out = Image::new(image.width, image.height);
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let mut sum = WeightedSum::default();
+                kernel(x, y, &mut sum);
+                let color = if sum.weight > 0.0 {
+                    Color(&sum.color / sum.weight)
+                } else {
+                    image.get(x, y).clone()
+                };
+                out.set(x, y, color);
+            }
+        }
+        out
+    }
+}
+
+#[derive(Default)]
+struct WeightedSum {
+    color: Vec3,
+    weight: f64,
+}
+
+impl WeightedSum {
+    fn add(&mut self, color: &Color, weight: f64) {
+        self.color += &color.0 * weight;
+        self.weight += weight;
+    }
+}
+
+impl std::str::FromStr for Denoiser {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bilateral" => Ok(Denoiser::Bilateral),
+            "atrous" => Ok(Denoiser::Atrous),
+            _ => Err(format!(
+                "unknown denoiser '{s}' (expected bilateral or atrous)"
+            )),
+        }
+    }
+}
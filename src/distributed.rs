@@ -0,0 +1,246 @@
+use std::{
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    process::{Command, Stdio},
+    sync::Mutex,
+    thread,
+};
+
+use crate::{
+    adaptive::PixelStats,
+    aov::AovPixel,
+    camera::Camera,
+    checkpoint::{
+        invalid, read_aov_pixel, read_film_pixel, read_line, read_stats, write_aov_pixel,
+        write_film_pixel, write_stats,
+    },
+    film::FilmPixel,
+    hittable::Hittable,
+    image::Rect,
+    progressive::RenderState,
+};
+
+pub fn tiles(bounds: &Rect, size: usize) -> Vec<Rect> {
+    let size = size.max(1);
+    let mut tiles = Vec::new();
+    for y0 in (bounds.y0..bounds.y1).step_by(size) {
+        for x0 in (bounds.x0..bounds.x1).step_by(size) {
+            tiles.push(Rect::new(
+                x0,
+                y0,
+                (x0 + size).min(bounds.x1),
+                (y0 + size).min(bounds.y1),
+            ));
+        }
+    }
+    tiles
+}
+
+pub fn run_worker(camera: &Camera, world: &dyn Hittable) -> io::Result<()> {
+    let mut out = BufWriter::new(io::stdout().lock());
+    // One full-frame state is reused for every tile and cleared where the tile touched it,
+    // instead of allocating film, stats and AOV buffers per request.
+    let mut state = camera.new_state(world);
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let tile: Rect = line
+            .strip_prefix("TILE ")
+            .ok_or_else(|| invalid(format!("unexpected request '{line}'")))?
+            .parse()
+            .map_err(invalid)?;
+        camera.render_tile(world, &mut state, &tile);
+        writeln!(out, "DONE {}", format_rect(&tile))?;
+        write_tile(&mut out, &state, &tile)?;
+        out.flush()?;
+        clear_tile(&mut state, &tile);
+    }
+    Ok(())
+}
+
+pub fn run_coordinator(
+    camera: &Camera,
+    state: &mut RenderState,
+    workers: usize,
+    tile_size: usize,
+    worker_args: &[String],
+) -> io::Result<()> {
+    let layout = TileLayout::of(state);
+    let mut queue = tiles(&camera.sample_bounds(), tile_size);
+    queue.reverse();
+    let pb = indicatif::ProgressBar::new(queue.len() as u64);
+    let queue = Mutex::new(queue);
+    let state = Mutex::new(state);
+    let executable = std::env::current_exe()?;
+
+    let errors: Vec<io::Error> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers.max(1))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut child = Command::new(&executable)
+                        .args(worker_args)
+                        .arg("--worker")
+                        .stdin(Stdio::piped())
+                        .stdout(Stdio::piped())
+                        .spawn()?;
+                    let mut requests = child.stdin.take().expect("worker stdin is piped");
+                    let mut responses =
+                        BufReader::new(child.stdout.take().expect("worker stdout is piped"));
+                    loop {
+                        let Some(tile) = queue.lock().unwrap().pop() else {
+                            break;
+                        };
+                        let result = request_tile(&mut requests, &mut responses, &layout, &tile);
+                        match result {
+                            Ok(result) => {
+                                result.merge_into(&mut state.lock().unwrap(), &tile);
+                                pb.inc(1);
+                            }
+                            Err(err) => {
+                                queue.lock().unwrap().push(tile);
+                                let _ = child.kill();
+                                let _ = child.wait();
+                                return Err(err);
+                            }
+                        }
+                    }
+                    drop(requests);
+                    child.wait()?;
+                    Ok(())
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().unwrap().err())
+            .collect()
+    });
+    pb.finish_with_message("done");
+
+    let remaining = queue.lock().unwrap().len();
+    match errors.into_iter().next() {
+        Some(err) if remaining > 0 => Err(io::Error::new(
+            err.kind(),
+            format!("{remaining} tiles left unrendered after worker failure: {err}"),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn request_tile(
+    requests: &mut impl Write,
+    responses: &mut impl BufRead,
+    layout: &TileLayout,
+    tile: &Rect,
+) -> io::Result<TileResult> {
+    writeln!(requests, "TILE {}", format_rect(tile))?;
+    requests.flush()?;
+    let line = read_line(responses)?;
+    if line != format!("DONE {}", format_rect(tile)) {
+        return Err(invalid(format!("unexpected worker response '{line}'")));
+    }
+    TileResult::read(responses, layout, tile)
+}
+
+fn format_rect(rect: &Rect) -> String {
+    format!("{},{},{},{}", rect.x0, rect.y0, rect.x1, rect.y1)
+}
+
+struct TileLayout {
+    width: usize,
+    height: usize,
+    margin: usize,
+    aovs: bool,
+}
+
+impl TileLayout {
+    fn of(state: &RenderState) -> Self {
+        Self {
+            width: state.film.width,
+            height: state.film.height,
+            margin: state.film.filter.margin(),
+            aovs: state.aov_pixels.is_some(),
+        }
+    }
+
+    fn splat_bounds(&self, tile: &Rect) -> Rect {
+        tile.expand(self.margin).clamp(self.width, self.height)
+    }
+}
+
+fn pixels(rect: &Rect, width: usize) -> impl Iterator<Item = usize> {
+    (rect.y0..rect.y1).flat_map(move |y| (rect.x0..rect.x1).map(move |x| y * width + x))
+}
+
+fn write_tile(out: &mut impl Write, state: &RenderState, tile: &Rect) -> io::Result<()> {
+    let width = state.film.width;
+    for index in pixels(&TileLayout::of(state).splat_bounds(tile), width) {
+        write_film_pixel(out, &state.film.pixels[index])?;
+    }
+    for index in pixels(tile, width) {
+        write_stats(out, &state.stats[index])?;
+    }
+    if let Some(aov_pixels) = &state.aov_pixels {
+        for index in pixels(tile, width) {
+            write_aov_pixel(out, &aov_pixels[index])?;
+        }
+    }
+    Ok(())
+}
+
+fn clear_tile(state: &mut RenderState, tile: &Rect) {
+    let width = state.film.width;
+    for index in pixels(&TileLayout::of(state).splat_bounds(tile), width) {
+        state.film.pixels[index] = FilmPixel::default();
+    }
+    for index in pixels(tile, width) {
+        state.stats[index] = PixelStats::default();
+    }
+    if let Some(aov_pixels) = state.aov_pixels.as_mut() {
+        for index in pixels(tile, width) {
+            aov_pixels[index] = AovPixel::default();
+        }
+    }
+}
+
+struct TileResult {
+    film: Vec<FilmPixel>,
+    stats: Vec<PixelStats>,
+    aovs: Option<Vec<AovPixel>>,
+}
+
+impl TileResult {
+    fn read(input: &mut impl Read, layout: &TileLayout, tile: &Rect) -> io::Result<Self> {
+        let film = (0..layout.splat_bounds(tile).area())
+            .map(|_| read_film_pixel(input))
+            .collect::<io::Result<_>>()?;
+        let stats = (0..tile.area())
+            .map(|_| read_stats(input))
+            .collect::<io::Result<_>>()?;
+        let aovs = if layout.aovs {
+            let pixels = (0..tile.area())
+                .map(|_| read_aov_pixel(input))
+                .collect::<io::Result<_>>()?;
+            Some(pixels)
+        } else {
+            None
+        };
+        Ok(Self { film, stats, aovs })
+    }
+
+    fn merge_into(self, state: &mut RenderState, tile: &Rect) {
+        let width = state.film.width;
+        let splat = TileLayout::of(state).splat_bounds(tile);
+        for (index, pixel) in pixels(&splat, width).zip(self.film) {
+            let target = &mut state.film.pixels[index];
+            target.sum += pixel.sum;
+            target.weight += pixel.weight;
+        }
+        for (index, stats) in pixels(tile, width).zip(self.stats) {
+            state.stats[index] = stats;
+        }
+        if let (Some(aov_pixels), Some(target)) = (self.aovs, state.aov_pixels.as_mut()) {
+            for (index, pixel) in pixels(tile, width).zip(aov_pixels) {
+                target[index] = pixel;
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{
+    color::Color,
+    filter::Filter,
+    image::{Image, Rect},
+    vec3::Vec3,
+};
+
+#[derive(Clone, Default)]
+pub struct FilmPixel {
+    pub sum: Vec3,
+    pub weight: f64,
+}
+
+pub struct Film {
+    pub width: usize,
+    pub height: usize,
+    pub filter: Filter,
+    pub pixels: Vec<FilmPixel>,
+}
+
+impl Film {
+    pub fn new(width: usize, height: usize, filter: Filter) -> Self {
+        Self {
+            width,
+            height,
+            filter,
+            pixels: vec![FilmPixel::default(); width * height],
+        }
+    }
+
+    // Splats a sample into the pixels its filter covers, limited to `bounds` so that samples of
+    // one stereo eye never bleed into the other.
+    pub fn add_sample(&mut self, (px, py): (f64, f64), color: &Color, bounds: &Rect) {
+        let radius = self.filter.radius;
+        let x0 = ((px - 0.5 - radius).ceil().max(bounds.x0 as f64)) as usize;
+        let y0 = ((py - 0.5 - radius).ceil().max(bounds.y0 as f64)) as usize;
+        let x1 = ((px - 0.5 + radius).floor()).min(bounds.x1.min(self.width) as f64 - 1.0);
+        let y1 = ((py - 0.5 + radius).floor()).min(bounds.y1.min(self.height) as f64 - 1.0);
+        if x1 < x0 as f64 || y1 < y0 as f64 {
+            return;
+        }
+        for y in y0..=y1 as usize {
+            for x in x0..=x1 as usize {
+                let weight = self
+                    .filter
+                    .evaluate(px - (x as f64 + 0.5), py - (y as f64 + 0.5));
+                if weight == 0.0 {
+                    continue;
+                }
+                let pixel = &mut self.pixels[y * self.width + x];
+                pixel.sum += &color.0 * weight;
+                pixel.weight += weight;
+            }
+        }
+    }
+
+    pub fn image(&self) -> Image {
+        Image {
+            width: self.width,
+            height: self.height,
+            pixels: self
+                .pixels
+                .iter()
+                .map(|pixel| {
+                    if pixel.weight > 0.0 {
+                        Color(&pixel.sum / pixel.weight)
+                    } else {
+                        Color::default()
+                    }
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::f64::consts::PI;
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum FilterKind {
+    #[default]
+    Box,
+    Tent,
+    Gaussian,
+    Mitchell,
+    Lanczos,
+}
+
+impl FilterKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FilterKind::Box => "box",
+            FilterKind::Tent => "tent",
+            FilterKind::Gaussian => "gaussian",
+            FilterKind::Mitchell => "mitchell",
+            FilterKind::Lanczos => "lanczos",
+        }
+    }
+
+    pub fn default_radius(&self) -> f64 {
+        match self {
+            FilterKind::Box => 0.5,
+            FilterKind::Tent => 1.0,
+            FilterKind::Gaussian => 1.5,
+            FilterKind::Mitchell => 2.0,
+            FilterKind::Lanczos => 3.0,
+        }
+    }
+}
+
+impl std::str::FromStr for FilterKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "box" => Ok(FilterKind::Box),
+            "tent" => Ok(FilterKind::Tent),
+            "gaussian" => Ok(FilterKind::Gaussian),
+            "mitchell" => Ok(FilterKind::Mitchell),
+            "lanczos" => Ok(FilterKind::Lanczos),
+            _ => Err(format!(
+                "unknown filter '{s}' (expected box, tent, gaussian, mitchell or lanczos)"
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Filter {
+    pub kind: FilterKind,
+    pub radius: f64,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self::new(FilterKind::default(), None)
+    }
+}
+
+impl Filter {
+    pub fn new(kind: FilterKind, radius: Option<f64>) -> Self {
+        Self {
+            kind,
+            radius: radius.unwrap_or_else(|| kind.default_radius()),
+        }
+    }
+
+    pub fn margin(&self) -> usize {
+        (self.radius - 0.5).ceil() as usize
+    }
+
+    pub fn evaluate(&self, dx: f64, dy: f64) -> f64 {
+        self.evaluate_1d(dx) * self.evaluate_1d(dy)
+    }
+
+    fn evaluate_1d(&self, x: f64) -> f64 {
+        let r = self.radius;
+        let x = x.abs();
+        if x >= r {
+            return 0.0;
+        }
+        match self.kind {
+            FilterKind::Box => 1.0,
+            FilterKind::Tent => r - x,
+            FilterKind::Gaussian => {
+                let sigma = r / 3.0;
+                let gaussian = |x: f64| (-x * x / (2.0 * sigma * sigma)).exp();
+                (gaussian(x) - gaussian(r)).max(0.0)
+            }
+            FilterKind::Mitchell => Self::mitchell(2.0 * x / r, 1.0 / 3.0, 1.0 / 3.0),
+            FilterKind::Lanczos => Self::sinc(x) * Self::sinc(x / r),
+        }
+    }
+
+    fn mitchell(x: f64, b: f64, c: f64) -> f64 {
+        let (x2, x3) = (x * x, x * x * x);
+        if x < 1.0 {
+            ((12.0 - 9.0 * b - 6.0 * c) * x3 + (-18.0 + 12.0 * b + 6.0 * c) * x2 + (6.0 - 2.0 * b))
+                / 6.0
+        } else {
+            ((-b - 6.0 * c) * x3
+                + (6.0 * b + 30.0 * c) * x2
+                + (-12.0 * b - 48.0 * c) * x
+                + (8.0 * b + 24.0 * c))
+                / 6.0
+        }
+    }
+
+    fn sinc(x: f64) -> f64 {
+        if x.abs() < 1e-5 {
+            1.0
+        } else {
+            (PI * x).sin() / (PI * x)
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use std::hash::Hasher;
+
+use crate::vec3::Vec3;
+
+// FNV-1a, chosen over `DefaultHasher` because its output must stay stable across builds and
+// machines for renders of the same scene to be recognised as mergeable.
+pub struct Fingerprint(u64);
+
+impl Default for Fingerprint {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Fingerprint {
+    pub fn write_str(&mut self, value: &str) {
+        self.write(value.as_bytes());
+        self.write_u8(0xff);
+    }
+
+    pub fn write_f64(&mut self, value: f64) {
+        self.write_u64(value.to_bits());
+    }
+
+    pub fn write_vec3(&mut self, value: &Vec3) {
+        self.write_f64(value.x);
+        self.write_f64(value.y);
+        self.write_f64(value.z);
+    }
+}
+
+impl Hasher for Fingerprint {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    // The default integer methods hash native-endian bytes of platform-sized values; fix them to
+    // little-endian and widen `usize` so every host produces the same fingerprint.
+    fn write_u16(&mut self, value: u16) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64);
+    }
+}
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use crate::{
+    fingerprint::Fingerprint,
     interval::Interval,
     material::Material,
     ray::Ray,
@@ -12,6 +13,10 @@ pub struct HitRecord {
     pub normal: Vec3,
     pub material: Arc<dyn Material>,
     pub time: f64,
+    pub u: f64,
+    pub v: f64,
+    pub object_id: usize,
+    pub material_id: Option<usize>,
     pub front_face: bool,
 }
 
@@ -22,6 +27,7 @@ impl HitRecord {
         outward_normal: Vec3,
         ray: &Ray,
         material: Arc<dyn Material>,
+        (u, v): (f64, f64),
     ) -> Self {
         let front_face = ray.direction.dot(&outward_normal) < 0.0;
         let normal = if front_face {
@@ -34,11 +40,21 @@ impl HitRecord {
             normal,
             material,
             time: hit_time,
+            u,
+            v,
+            object_id: 0,
+            material_id: None,
             front_face,
         }
     }
 }
 
-pub trait Hittable {
+pub trait Hittable: Send + Sync {
     fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord>;
+
+    fn material(&self) -> Option<Arc<dyn Material>> {
+        None
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint);
 }
@@ -1,26 +1,50 @@
-use std::{cmp::Ordering, sync::Arc};
+use std::{cmp::Ordering, hash::Hasher, sync::Arc};
 
 use crate::{
+    fingerprint::Fingerprint,
     hittable::{HitRecord, Hittable},
     interval::Interval,
     ray::Ray,
 };
 
+// Material IDs number the distinct material fingerprints in object order, so they depend only
+// on the scene and not on which objects a render happens to hit first.
 #[derive(Default)]
 pub struct HittableList {
     pub objects: Vec<Arc<dyn Hittable>>,
+    material_ids: Vec<Option<usize>>,
+    materials: Vec<u64>,
 }
 
 impl HittableList {
     pub fn new(objects: Vec<Arc<dyn Hittable>>) -> Self {
-        Self { objects }
+        let mut list = Self::default();
+        for object in objects {
+            list.add(object);
+        }
+        list
     }
 
     pub fn clear(&mut self) {
         self.objects.clear();
+        self.material_ids.clear();
+        self.materials.clear();
     }
 
     pub fn add(&mut self, object: Arc<dyn Hittable>) {
+        let material_id = object.material().map(|material| {
+            let mut fingerprint = Fingerprint::default();
+            material.fingerprint(&mut fingerprint);
+            let hash = fingerprint.finish();
+            match self.materials.iter().position(|material| *material == hash) {
+                Some(id) => id,
+                None => {
+                    self.materials.push(hash);
+                    self.materials.len() - 1
+                }
+            }
+        });
+        self.material_ids.push(material_id);
         self.objects.push(object);
     }
 }
@@ -29,7 +53,14 @@ impl Hittable for HittableList {
     fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
         self.objects
             .iter()
-            .filter_map(|object| object.hit(ray, interval))
+            .enumerate()
+            .filter_map(|(object_id, object)| {
+                object.hit(ray, interval).map(|hit| HitRecord {
+                    object_id,
+                    material_id: self.material_ids.get(object_id).copied().flatten(),
+                    ..hit
+                })
+            })
             .min_by(|x, y| {
                 if x.time < y.time {
                     Ordering::Less
@@ -38,4 +69,12 @@ impl Hittable for HittableList {
                 }
             })
     }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_str("HittableList");
+        fingerprint.write_usize(self.objects.len());
+        for object in &self.objects {
+            object.fingerprint(fingerprint);
+        }
+    }
 }
@@ -0,0 +1,207 @@
+use std::io::{self, Read, Write};
+
+use crate::{color::Color, tonemap::ToneMapper};
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x0: usize,
+    pub y0: usize,
+    pub x1: usize,
+    pub y1: usize,
+}
+
+impl Rect {
+    pub fn new(x0: usize, y0: usize, x1: usize, y1: usize) -> Self {
+        Self { x0, y0, x1, y1 }
+    }
+
+    pub fn width(&self) -> usize {
+        self.x1.saturating_sub(self.x0)
+    }
+
+    pub fn height(&self) -> usize {
+        self.y1.saturating_sub(self.y0)
+    }
+
+    pub fn area(&self) -> usize {
+        self.width() * self.height()
+    }
+
+    pub fn clamp(&self, width: usize, height: usize) -> Self {
+        Self::new(
+            self.x0.min(width),
+            self.y0.min(height),
+            self.x1.min(width),
+            self.y1.min(height),
+        )
+    }
+
+    pub fn expand(&self, by: usize) -> Self {
+        Self::new(
+            self.x0.saturating_sub(by),
+            self.y0.saturating_sub(by),
+            self.x1 + by,
+            self.y1 + by,
+        )
+    }
+}
+
+impl std::str::FromStr for Rect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(|value| value.trim().parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| format!("invalid rectangle '{s}': {err}"))?;
+        match values[..] {
+            [x0, y0, x1, y1] if x0 < x1 && y0 < y1 => Ok(Rect::new(x0, y0, x1, y1)),
+            _ => Err(format!(
+                "invalid rectangle '{s}' (expected x0,y0,x1,y1 with x0 < x1 and y0 < y1)"
+            )),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Color>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![Color::default(); width * height],
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> &Color {
+        &self.pixels[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Color) {
+        self.pixels[y * self.width + x] = color;
+    }
+
+    pub fn crop(&self, rect: &Rect) -> Image {
+        let mut out = Image::new(rect.width(), rect.height());
+        for y in 0..rect.height() {
+            for x in 0..rect.width() {
+                out.set(x, y, self.get(rect.x0 + x, rect.y0 + y).clone());
+            }
+        }
+        out
+    }
+
+    // Reads a binary or plain PGM/PPM file with values normalized to [0, 1] and left in the
+    // file's encoding.
+    pub fn read_pnm(input: &mut impl Read) -> io::Result<Image> {
+        let invalid =
+            |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+
+        let mut position = 0;
+        let mut header = Vec::new();
+        while header.len() < 4 {
+            while position < data.len() && data[position].is_ascii_whitespace() {
+                position += 1;
+            }
+            if data.get(position) == Some(&b'#') {
+                while position < data.len() && data[position] != b'\n' {
+                    position += 1;
+                }
+                continue;
+            }
+            let start = position;
+            while position < data.len() && !data[position].is_ascii_whitespace() {
+                position += 1;
+            }
+            if start == position {
+                return Err(invalid("truncated PNM header"));
+            }
+            header.push(String::from_utf8_lossy(&data[start..position]).into_owned());
+        }
+        let dimension = |value: &str| {
+            value
+                .parse::<usize>()
+                .map_err(|_| invalid("invalid PNM header"))
+        };
+        let (width, height, max) = (
+            dimension(&header[1])?,
+            dimension(&header[2])?,
+            dimension(&header[3])?,
+        );
+        let (channels, binary) = match header[0].as_str() {
+            "P2" => (1, false),
+            "P3" => (3, false),
+            "P5" => (1, true),
+            "P6" => (3, true),
+            _ => {
+                return Err(invalid(
+                    "unsupported PNM format (expected P2, P3, P5 or P6)",
+                ));
+            }
+        };
+        if max == 0 || max > 65535 {
+            return Err(invalid("invalid PNM maximum value"));
+        }
+
+        let count = width * height * channels;
+        let values: Vec<usize> = if binary {
+            let bytes = if max < 256 { 1 } else { 2 };
+            let body = data
+                .get(position + 1..position + 1 + count * bytes)
+                .ok_or_else(|| invalid("truncated PNM data"))?;
+            body.chunks(bytes)
+                .map(|chunk| {
+                    chunk
+                        .iter()
+                        .fold(0, |value, byte| value << 8 | *byte as usize)
+                })
+                .collect()
+        } else {
+            String::from_utf8_lossy(&data[position..])
+                .split_ascii_whitespace()
+                .take(count)
+                .map(dimension)
+                .collect::<io::Result<_>>()?
+        };
+        if values.len() < count {
+            return Err(invalid("truncated PNM data"));
+        }
+
+        let mut image = Image::new(width, height);
+        for (pixel, value) in image.pixels.iter_mut().zip(values.chunks(channels)) {
+            let component = |index: usize| value[index.min(channels - 1)] as f64 / max as f64;
+            *pixel = Color::new(component(0), component(1), component(2));
+        }
+        Ok(image)
+    }
+
+    pub fn write_ppm(&self, out: &mut impl Write, tone_mapper: &ToneMapper) -> io::Result<()> {
+        writeln!(out, "P3\n{} {}\n255", self.width, self.height)?;
+        for pixel in &self.pixels {
+            write!(out, "{}", tone_mapper.apply(pixel))?;
+        }
+        Ok(())
+    }
+
+    pub fn write_pfm(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "PF\n{} {}\n-1.0", self.width, self.height)?;
+        for y in (0..self.height).rev() {
+            for x in 0..self.width {
+                let pixel = self.get(x, y);
+                for component in [pixel.x, pixel.y, pixel.z] {
+                    out.write_all(&(component as f32).to_le_bytes())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
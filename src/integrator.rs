@@ -0,0 +1,268 @@
+use std::hash::Hasher;
+
+use crate::{
+    color::Color,
+    fingerprint::Fingerprint,
+    hittable::Hittable,
+    interval::Interval,
+    ray::Ray,
+    sampler::Sampler,
+    vec3::{Point3, Vec3},
+};
+
+pub trait Integrator: Send + Sync {
+    fn ray_color(
+        &self,
+        ray: &Ray,
+        world: &dyn Hittable,
+        depth: i32,
+        sampler: &mut dyn Sampler,
+    ) -> Color;
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint);
+}
+
+fn hit_interval() -> Interval {
+    Interval::new(0.001, f64::INFINITY)
+}
+
+pub fn sky(ray: &Ray) -> Color {
+    let unit_direction = ray.direction.unit();
+    let a = 0.5 * unit_direction.y + 1.0;
+    Color::new(1.0, 1.0, 1.0) * (1.0 - a) + Color::new(0.5, 0.7, 1.0) * a
+}
+
+#[derive(Default)]
+pub struct PathTracer;
+
+impl Integrator for PathTracer {
+    fn ray_color(
+        &self,
+        ray: &Ray,
+        world: &dyn Hittable,
+        depth: i32,
+        sampler: &mut dyn Sampler,
+    ) -> Color {
+        if depth <= 0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        if let Some(hit) = world.hit(ray, &hit_interval()) {
+            let emitted = hit.material.emitted(&hit);
+            return if let Some(scatter) = hit.material.scatter(ray, &hit, sampler) {
+                emitted
+                    + scatter.attenuation * self.ray_color(&scatter.ray, world, depth - 1, sampler)
+            } else {
+                emitted
+            };
+        }
+        sky(ray)
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_str("PathTracer");
+    }
+}
+
+#[derive(Default)]
+pub struct Normals;
+
+impl Integrator for Normals {
+    fn ray_color(
+        &self,
+        ray: &Ray,
+        world: &dyn Hittable,
+        _depth: i32,
+        _sampler: &mut dyn Sampler,
+    ) -> Color {
+        match world.hit(ray, &hit_interval()) {
+            Some(hit) => Color((&hit.normal + 1.0) * 0.5),
+            None => Color::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_str("Normals");
+    }
+}
+
+pub struct Depth {
+    max_distance: f64,
+}
+
+impl Depth {
+    pub fn new(max_distance: f64) -> Self {
+        Self { max_distance }
+    }
+}
+
+impl Default for Depth {
+    fn default() -> Self {
+        Self::new(10.0)
+    }
+}
+
+impl Integrator for Depth {
+    fn ray_color(
+        &self,
+        ray: &Ray,
+        world: &dyn Hittable,
+        _depth: i32,
+        _sampler: &mut dyn Sampler,
+    ) -> Color {
+        match world.hit(ray, &hit_interval()) {
+            Some(hit) => {
+                let distance = hit.time * ray.direction.len();
+                let d = Interval::new(0.0, 1.0).clamp(distance / self.max_distance);
+                Color::new(d, d, d)
+            }
+            None => Color::new(1.0, 1.0, 1.0),
+        }
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_str("Depth");
+        fingerprint.write_f64(self.max_distance);
+    }
+}
+
+#[derive(Default)]
+pub struct Albedo;
+
+impl Integrator for Albedo {
+    fn ray_color(
+        &self,
+        ray: &Ray,
+        world: &dyn Hittable,
+        _depth: i32,
+        _sampler: &mut dyn Sampler,
+    ) -> Color {
+        match world.hit(ray, &hit_interval()) {
+            Some(hit) => hit.material.albedo(&hit),
+            None => sky(ray),
+        }
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_str("Albedo");
+    }
+}
+
+#[derive(Default)]
+pub struct Uv;
+
+impl Integrator for Uv {
+    fn ray_color(
+        &self,
+        ray: &Ray,
+        world: &dyn Hittable,
+        _depth: i32,
+        _sampler: &mut dyn Sampler,
+    ) -> Color {
+        match world.hit(ray, &hit_interval()) {
+            Some(hit) => Color::new(hit.u, hit.v, 0.0),
+            None => Color::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_str("Uv");
+    }
+}
+
+pub struct AmbientOcclusion {
+    samples: i32,
+    max_distance: f64,
+}
+
+impl AmbientOcclusion {
+    pub fn new(samples: i32, max_distance: f64) -> Self {
+        Self {
+            samples: samples.max(1),
+            max_distance,
+        }
+    }
+
+    fn occluded(world: &dyn Hittable, point: &Point3, direction: Vec3, max_distance: f64) -> bool {
+        let ray = Ray::new(point.clone(), direction.unit());
+        world
+            .hit(&ray, &Interval::new(0.001, max_distance))
+            .is_some()
+    }
+}
+
+impl Default for AmbientOcclusion {
+    fn default() -> Self {
+        Self::new(16, 1.0)
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn ray_color(
+        &self,
+        ray: &Ray,
+        world: &dyn Hittable,
+        _depth: i32,
+        sampler: &mut dyn Sampler,
+    ) -> Color {
+        let Some(hit) = world.hit(ray, &hit_interval()) else {
+            return Color::new(1.0, 1.0, 1.0);
+        };
+        let visible = (0..self.samples)
+            .filter(|_| {
+                let direction = &hit.normal + &Vec3::unit_from_sample(sampler.get_2d());
+                let direction = if direction.near_zero() {
+                    hit.normal.clone()
+                } else {
+                    direction
+                };
+                !Self::occluded(world, &hit.point, direction, self.max_distance)
+            })
+            .count();
+        let a = visible as f64 / self.samples as f64;
+        Color::new(a, a, a)
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_str("AmbientOcclusion");
+        fingerprint.write_i32(self.samples);
+        fingerprint.write_f64(self.max_distance);
+    }
+}
+
+#[derive(Default)]
+pub struct BounceHeatmap;
+
+impl BounceHeatmap {
+    fn bounces(ray: &Ray, world: &dyn Hittable, depth: i32, sampler: &mut dyn Sampler) -> i32 {
+        let mut ray = ray.clone();
+        let mut bounces = 0;
+        while bounces < depth {
+            let Some(hit) = world.hit(&ray, &hit_interval()) else {
+                break;
+            };
+            bounces += 1;
+            match hit.material.scatter(&ray, &hit, sampler) {
+                Some(scatter) => ray = scatter.ray,
+                None => break,
+            }
+        }
+        bounces
+    }
+}
+
+impl Integrator for BounceHeatmap {
+    fn ray_color(
+        &self,
+        ray: &Ray,
+        world: &dyn Hittable,
+        depth: i32,
+        sampler: &mut dyn Sampler,
+    ) -> Color {
+        let bounces = Self::bounces(ray, world, depth, sampler);
+        Color::heatmap(bounces as f64 / depth.max(1) as f64)
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_str("BounceHeatmap");
+    }
+}
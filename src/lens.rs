@@ -0,0 +1,50 @@
+use crate::fingerprint::Fingerprint;
+
+// Imperfections of a real lens on top of the ideal perspective projection. Distortion follows
+// the Brown-Conrady radial model in focal-normalized image coordinates, chromatic aberration
+// magnifies the red channel and shrinks the blue one by the given fraction, shift moves the
+// image window in fractions of its size and tilt rotates the plane of focus about the
+// horizontal axis, in degrees, so that positive values bring the bottom of the frame closer.
+#[derive(Clone, Copy, Default)]
+pub struct Lens {
+    pub k1: f64,
+    pub k2: f64,
+    pub chromatic_aberration: f64,
+    pub shift: (f64, f64),
+    pub tilt: f64,
+}
+
+impl Lens {
+    pub fn distorts(&self) -> bool {
+        self.k1 != 0.0 || self.k2 != 0.0 || self.chromatic_aberration != 0.0
+    }
+
+    // Maps a point of the distorted image seen in `channel` back to the undistorted ray
+    // through it. The radial model has no closed-form inverse, so iterate to its fixed point.
+    pub fn undistort(&self, (x, y): (f64, f64), channel: Option<usize>) -> (f64, f64) {
+        let magnification = match channel {
+            Some(channel) => 1.0 + self.chromatic_aberration * (1.0 - channel as f64),
+            None => 1.0,
+        };
+        let (x, y) = (x / magnification, y / magnification);
+        let (mut ux, mut uy) = (x, y);
+        for _ in 0..20 {
+            let r2 = ux * ux + uy * uy;
+            let scale = 1.0 + self.k1 * r2 + self.k2 * r2 * r2;
+            if scale <= 0.0 {
+                break;
+            }
+            (ux, uy) = (x / scale, y / scale);
+        }
+        (ux, uy)
+    }
+
+    pub fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_f64(self.k1);
+        fingerprint.write_f64(self.k2);
+        fingerprint.write_f64(self.chromatic_aberration);
+        fingerprint.write_f64(self.shift.0);
+        fingerprint.write_f64(self.shift.1);
+        fingerprint.write_f64(self.tilt);
+    }
+}
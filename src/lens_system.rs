@@ -0,0 +1,308 @@
+use std::{fs, io};
+
+use crate::{fingerprint::Fingerprint, ray::Ray, vec3::Vec3};
+
+// Double Gauss 50 mm f/2 (US patent 2,673,491), scaled from 100 mm.
+const DOUBLE_GAUSS_50MM: &str = "
+29.475   3.76   1.67   25.2
+84.83    0.12   1      25.2
+19.275   4.025  1.67   23
+40.77    3.275  1.699  23
+12.75    5.705  1      18
+0        4.5    0      17.1
+-14.495  1.18   1.603  17
+40.77    6.065  1.658  20
+-20.385  0.19   1      20
+437.065  3.22   1.717  20
+-39.73   5      1      20
+";
+
+// One refracting surface of a lens prescription, listed from the scene towards the film, in
+// millimeters. The thickness is the distance to the next surface (to the film for the last one)
+// and the index of refraction is that of the medium behind the surface. A zero radius marks
+// the flat aperture stop.
+#[derive(Clone, Copy)]
+pub struct LensElement {
+    pub radius: f64,
+    pub thickness: f64,
+    pub ior: f64,
+    pub aperture_radius: f64,
+}
+
+// Traces rays from the film through a multi-element lens. Lens space has the film at z = 0 and
+// the lens along -z, so rays leave the film travelling towards negative z.
+pub struct LensSystem {
+    elements: Vec<LensElement>,
+    film_width: f64,
+    film_height: f64,
+    center_transmission: f64,
+}
+
+impl LensSystem {
+    pub fn load(name: &str) -> io::Result<Vec<LensElement>> {
+        let prescription = match name {
+            "double-gauss" => DOUBLE_GAUSS_50MM.to_string(),
+            path => fs::read_to_string(path)?,
+        };
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut elements = Vec::new();
+        for line in prescription.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let values = line
+                .split_whitespace()
+                .map(str::parse::<f64>)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| invalid(format!("invalid lens element '{line}': {err}")))?;
+            let [radius, thickness, ior, aperture] = values[..] else {
+                return Err(invalid(format!(
+                    "invalid lens element '{line}' (expected radius, thickness, ior and aperture)"
+                )));
+            };
+            elements.push(LensElement {
+                radius,
+                thickness,
+                ior,
+                aperture_radius: aperture / 2.0,
+            });
+        }
+        if elements.is_empty() {
+            return Err(invalid(format!(
+                "lens prescription '{name}' has no elements"
+            )));
+        }
+        Ok(elements)
+    }
+
+    // Fits the sensor to the image aspect ratio like the pinhole camera does and moves the film
+    // so that the lens focuses at `focus_distance` meters.
+    pub fn new(
+        elements: Vec<LensElement>,
+        (sensor_width, sensor_height): (f64, f64),
+        aspect_ratio: f64,
+        focus_distance: f64,
+    ) -> io::Result<Self> {
+        let (film_width, film_height) = if aspect_ratio >= sensor_width / sensor_height {
+            (sensor_width, sensor_width / aspect_ratio)
+        } else {
+            (sensor_height * aspect_ratio, sensor_height)
+        };
+        let mut system = Self {
+            elements,
+            film_width,
+            film_height,
+            center_transmission: 1.0,
+        };
+        let back_focus = system.focus(focus_distance * 1000.0).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("lens cannot focus at {focus_distance} m"),
+            )
+        })?;
+        system.elements.last_mut().unwrap().thickness = back_focus;
+        system.center_transmission = system.transmission();
+        if system.center_transmission <= 0.0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "no light passes through the lens",
+            ));
+        }
+        Ok(system)
+    }
+
+    // Returns the ray leaving the front of the lens for normalized film coordinates (s to the
+    // right, t downwards) in camera space (x right, y up, z forward) and meters, together with
+    // the cos^4 falloff relative to the film center. Rays blocked by an element return nothing.
+    pub fn ray(&self, (s, t): (f64, f64), lens_sample: (f64, f64)) -> Option<(Ray, f64)> {
+        // The lens forms an inverted image: film points left of and below the axis see the
+        // scene to the right and above, and the image rows run downwards.
+        let film = Vec3::new(
+            (0.5 - s) * self.film_width,
+            (t - 0.5) * self.film_height,
+            0.0,
+        );
+        let rear = self.elements.last().unwrap();
+        let disk = Vec3::unit_disk_from_sample(lens_sample);
+        let pupil = Vec3::new(
+            disk.x * rear.aperture_radius,
+            disk.y * rear.aperture_radius,
+            -rear.thickness,
+        );
+        let direction = (&pupil - &film).unit();
+        let out = self.trace_from_film(Ray::new(film, direction.clone()))?;
+        let cos_theta = direction.z.abs();
+        let weight = cos_theta.powi(4) / self.center_transmission;
+        let origin = &out.origin / 1000.0;
+        Some((
+            Ray::new(
+                Vec3::new(origin.x, origin.y, -origin.z),
+                Vec3::new(out.direction.x, out.direction.y, -out.direction.z),
+            ),
+            weight,
+        ))
+    }
+
+    pub fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        for element in &self.elements {
+            fingerprint.write_f64(element.radius);
+            fingerprint.write_f64(element.thickness);
+            fingerprint.write_f64(element.ior);
+            fingerprint.write_f64(element.aperture_radius);
+        }
+        fingerprint.write_f64(self.film_width);
+        fingerprint.write_f64(self.film_height);
+    }
+
+    // Fraction of the rear element's area that lets light from the film center through, used to
+    // normalize image brightness.
+    fn transmission(&self) -> f64 {
+        let steps = 64;
+        let mut passed = 0;
+        for i in 0..steps {
+            for j in 0..steps {
+                let sample = (
+                    (i as f64 + 0.5) / steps as f64,
+                    (j as f64 + 0.5) / steps as f64,
+                );
+                if self.ray((0.5, 0.5), sample).is_some() {
+                    passed += 1;
+                }
+            }
+        }
+        passed as f64 / (steps * steps) as f64
+    }
+
+    fn trace_from_film(&self, mut ray: Ray) -> Option<Ray> {
+        let mut z = 0.0;
+        for (i, element) in self.elements.iter().enumerate().rev() {
+            z -= element.thickness;
+            let ior_behind = element.ior;
+            let ior_front = match i {
+                0 => 1.0,
+                _ => self.elements[i - 1].ior,
+            };
+            ray = Self::refract_at(element, z, &ray, ior_behind, ior_front)?;
+        }
+        Some(ray)
+    }
+
+    fn trace_from_scene(&self, mut ray: Ray) -> Option<Ray> {
+        let mut z = -self
+            .elements
+            .iter()
+            .map(|element| element.thickness)
+            .sum::<f64>();
+        for (i, element) in self.elements.iter().enumerate() {
+            let ior_front = match i {
+                0 => 1.0,
+                _ => self.elements[i - 1].ior,
+            };
+            ray = Self::refract_at(element, z, &ray, ior_front, element.ior)?;
+            z += element.thickness;
+        }
+        Some(ray)
+    }
+
+    // Intersects the surface whose vertex sits at `z` and refracts the ray from the medium with
+    // `ior_from` into the one with `ior_to`; the aperture stop only clips.
+    fn refract_at(
+        element: &LensElement,
+        z: f64,
+        ray: &Ray,
+        ior_from: f64,
+        ior_to: f64,
+    ) -> Option<Ray> {
+        let direction = ray.direction.unit();
+        let (t, normal) = if element.radius == 0.0 {
+            ((z - ray.origin.z) / direction.z, None)
+        } else {
+            let center = Vec3::new(0.0, 0.0, z + element.radius);
+            let oc = &ray.origin - &center;
+            let half_b = oc.dot(&direction);
+            let c = oc.len_squared() - element.radius * element.radius;
+            let discriminant = half_b * half_b - c;
+            if discriminant < 0.0 {
+                return None;
+            }
+            let (t0, t1) = (-half_b - discriminant.sqrt(), -half_b + discriminant.sqrt());
+            // The vertex cap is the near side of the sphere exactly when the ray travels
+            // towards the center of curvature.
+            let t = if (direction.z > 0.0) != (element.radius < 0.0) {
+                t0
+            } else {
+                t1
+            };
+            let normal = (&ray.at(t) - &center).unit();
+            let normal = if normal.dot(&direction) > 0.0 {
+                -normal
+            } else {
+                normal
+            };
+            (t, Some(normal))
+        };
+        if !t.is_finite() || t < 0.0 {
+            return None;
+        }
+        let point = ray.origin.clone() + &direction * t;
+        if point.x * point.x + point.y * point.y > element.aperture_radius * element.aperture_radius
+        {
+            return None;
+        }
+        let Some(normal) = normal else {
+            return Some(Ray::new(point, direction));
+        };
+
+        let air = |ior: f64| if ior == 0.0 { 1.0 } else { ior };
+        let eta = air(ior_from) / air(ior_to);
+        let cos_theta = -direction.dot(&normal);
+        if eta * eta * (1.0 - cos_theta * cos_theta) > 1.0 {
+            return None;
+        }
+        Some(Ray::new(point, Vec3::refract(&direction, &normal, eta)))
+    }
+
+    // Thick lens approximation: finds the focal and principal planes on both sides by tracing
+    // rays parallel to the axis, then solves the thick lens equation for the film distance that
+    // brings `focus_distance` (millimeters from the film) into focus.
+    fn focus(&self, focus_distance: f64) -> Option<f64> {
+        let length: f64 = self.elements.iter().map(|element| element.thickness).sum();
+        let rear = self.elements.last().unwrap().thickness;
+        let height = 0.001 * self.film_width.hypot(self.film_height);
+
+        let scene = Ray::new(
+            Vec3::new(height, 0.0, -length - 1.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        );
+        let (front_principal, front_focal) =
+            Self::cardinal_points(&scene, &self.trace_from_scene(scene.clone())?);
+        let film = Ray::new(
+            Vec3::new(height, 0.0, -rear + 1.0),
+            Vec3::new(0.0, 0.0, -1.0),
+        );
+        let (rear_principal, _) =
+            Self::cardinal_points(&film, &self.trace_from_film(film.clone())?);
+
+        let focal_length = front_focal - front_principal;
+        let z = -focus_distance;
+        let c = (rear_principal - z - front_principal)
+            * (rear_principal - z - 4.0 * focal_length - front_principal);
+        if c < 0.0 {
+            return None;
+        }
+        let delta = 0.5 * (rear_principal - z + front_principal - c.sqrt());
+        let back_focus = rear + delta;
+        (back_focus > 0.0).then_some(back_focus)
+    }
+
+    // Principal plane and focal point of a ray entering parallel to the axis, as lens space z
+    // positions.
+    fn cardinal_points(ray_in: &Ray, ray_out: &Ray) -> (f64, f64) {
+        let focal = ray_out.at(-ray_out.origin.x / ray_out.direction.x).z;
+        let principal = ray_out
+            .at((ray_in.origin.x - ray_out.origin.x) / ray_out.direction.x)
+            .z;
+        (principal, focal)
+    }
+}
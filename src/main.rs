@@ -1,49 +1,109 @@
-use std::sync::Arc;
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter},
+    sync::Arc,
+};
 
-use camera::Camera;
+use adaptive::sample_heatmap;
+use animation::Animation;
+
+use camera::{Camera, Render};
 use color::Color;
+use hittable::Hittable;
 use hittable_list::HittableList;
-use material::{Dielectric, Lambertian, Metal};
+use image::Rect;
+use material::{Dielectric, Lambertian, Material, Metal};
+use options::Options;
+use progressive::RenderState;
 use sphere::Sphere;
+use tonemap::ToneMapper;
 use vec3::Point3;
 
+pub mod adaptive;
+pub mod animated;
+pub mod animation;
+pub mod aov;
+pub mod aperture;
 pub mod camera;
+pub mod checkpoint;
 pub mod color;
+pub mod denoise;
+pub mod distributed;
+pub mod film;
+pub mod filter;
+pub mod fingerprint;
 pub mod hittable;
 pub mod hittable_list;
+pub mod image;
+pub mod integrator;
 pub mod interval;
+pub mod lens;
+pub mod lens_system;
 pub mod material;
+pub mod microfacet;
+pub mod options;
+pub mod physical;
+pub mod progressive;
+pub mod projection;
 pub mod ray;
+pub mod sampler;
 pub mod sphere;
+pub mod stereo;
+pub mod texture;
+pub mod tonemap;
 pub mod vec3;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match Options::parse(args.iter().cloned()) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("error: {err}");
+            std::process::exit(2);
+        }
+    };
+
+    if let Some(inputs) = &options.merge {
+        merge(&options, inputs);
+        return;
+    }
+
     // World
     let mut world = HittableList::default();
+    let material = |index, default: Arc<dyn Material>| {
+        exit_on_error(
+            options.material(index, default),
+            "failed to load material texture",
+        )
+    };
     let ground = Sphere::new(
         Point3::new(0.0, -100.5, -1.0),
         100.0,
-        Lambertian::new(Color::new(0.8, 0.8, 0.0)),
+        material(0, Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.0)))),
     );
 
     let center = Sphere::new(
         Point3::new(0.0, 0.0, -1.2),
         0.5,
-        Lambertian::new(Color::new(0.1, 0.2, 0.5)),
+        material(1, Arc::new(Lambertian::new(Color::new(0.1, 0.2, 0.5)))),
     );
 
-    let left = Sphere::new(Point3::new(-1.0, 0.0, -1.0), 0.5, Dielectric::new(1.5));
+    let left = Sphere::new(
+        Point3::new(-1.0, 0.0, -1.0),
+        0.5,
+        material(2, Arc::new(Dielectric::new(1.5))),
+    );
 
     let bubble = Sphere::new(
         Point3::new(-1.0, 0.0, -1.0),
         0.4,
-        Dielectric::new(1.0 / 1.5),
+        material(3, Arc::new(Dielectric::new(1.0 / 1.5))),
     );
 
     let right = Sphere::new(
         Point3::new(1.0, 0.0, -1.0),
         0.5,
-        Metal::new(Color::new(0.8, 0.6, 0.2), 1.0),
+        material(4, Arc::new(Metal::new(Color::new(0.8, 0.6, 0.2), 1.0))),
     );
 
     world.add(Arc::new(ground));
@@ -51,8 +111,218 @@ fn main() {
     world.add(Arc::new(left));
     world.add(Arc::new(bubble));
     world.add(Arc::new(right));
+    if let Some((index, _)) = options
+        .materials
+        .iter()
+        .find(|(index, _)| *index >= world.objects.len())
+    {
+        eprintln!(
+            "error: --material object index {index} is out of range (the scene has {} objects)",
+            world.objects.len()
+        );
+        std::process::exit(2);
+    }
 
     // Camera
-    let camera = Camera::new(16.0 / 9.0, 800, 800);
-    camera.render(Arc::new(world));
+    let aspect_ratio = 16.0 / 9.0;
+    let camera = Camera::new(aspect_ratio, options.width, options.samples_per_pixel)
+        .with_physical(options.physical)
+        .with_aperture(exit_on_error(
+            options.aperture.build(),
+            "failed to load aperture image",
+        ))
+        .with_lens(options.lens)
+        .with_lens_system(exit_on_error(
+            options.lens_system(aspect_ratio),
+            "failed to load lens system",
+        ))
+        .with_rolling_shutter(options.rolling_shutter)
+        .with_projection(options.projection)
+        .with_stereo(options.stereo())
+        .with_integrator(options.integrator.build())
+        .with_aovs(options.aovs.is_some() || options.denoise.is_some())
+        .with_adaptive_sampling(options.adaptive_sampling())
+        .with_sampler(options.sampler, options.seed)
+        .with_filter(options.filter())
+        .with_crop(options.crop);
+    if camera.crop().is_some_and(|crop| crop.area() == 0) {
+        let (width, height) = camera.film_size();
+        eprintln!("error: --crop does not overlap the {width}x{height} image");
+        std::process::exit(2);
+    }
+    let output_crop = camera.crop().filter(|_| !options.crop_full_frame);
+
+    if let Some(path) = &options.animation {
+        render_animation(&options, &camera, &world, path, output_crop.as_ref());
+        return;
+    }
+
+    if options.worker {
+        exit_on_error(distributed::run_worker(&camera, &world), "worker failed");
+        return;
+    }
+
+    let mut state = match &options.resume {
+        Some(path) => resume(&camera, &world, path),
+        None => camera.new_state(&world),
+    };
+    match options.workers {
+        Some(workers) => exit_on_error(
+            distributed::run_coordinator(&camera, &mut state, workers, options.tile_size, &args),
+            "distributed render failed",
+        ),
+        None => camera.render_progressive(
+            Arc::new(world),
+            &mut state,
+            &options.progressive(),
+            |state| {
+                if options.output.is_some() {
+                    write_outputs(&options, &state.render(), output_crop.as_ref(), None, true);
+                }
+                write_checkpoint(&options, state);
+            },
+        ),
+    }
+    write_outputs(&options, &state.render(), output_crop.as_ref(), None, false);
+    write_checkpoint(&options, &state);
+}
+
+fn render_animation(
+    options: &Options,
+    camera: &Camera,
+    world: &HittableList,
+    path: &str,
+    output_crop: Option<&Rect>,
+) {
+    let animation = exit_on_error(Animation::load(path), "failed to load animation");
+    let (first, last) = options.frames.unwrap_or(animation.frames);
+    // Ray times are seconds within the shutter; without a physical camera expose each frame for
+    // half its duration like a 180 degree film shutter.
+    let camera = match options.physical {
+        Some(_) => camera.clone(),
+        None => camera.clone().with_shutter(0.5 / animation.fps),
+    };
+    for frame in first..=last {
+        eprintln!("rendering frame {frame} ({first}-{last})");
+        let camera = animation.camera(&camera, frame as f64);
+        let world = animation.world(world, frame as f64);
+        let mut state = camera.new_state(&world);
+        camera.render_progressive(
+            Arc::new(world),
+            &mut state,
+            &options.progressive(),
+            |state| write_outputs(options, &state.render(), output_crop, Some(frame), true),
+        );
+        write_outputs(options, &state.render(), output_crop, Some(frame), false);
+    }
+}
+
+fn load_state(path: &str) -> RenderState {
+    exit_on_error(
+        File::open(path).and_then(|file| checkpoint::load(&mut BufReader::new(file))),
+        &format!("failed to read checkpoint {path}"),
+    )
+}
+
+fn merge(options: &Options, inputs: &[String]) {
+    let Some((first, rest)) = inputs.split_first() else {
+        eprintln!("error: merge requires at least one checkpoint file");
+        std::process::exit(2);
+    };
+    let mut merged = load_state(first);
+    for path in rest {
+        if let Err(err) = merged.merge(load_state(path)) {
+            eprintln!("error: cannot merge {path}: {err}");
+            std::process::exit(2);
+        }
+    }
+    write_outputs(options, &merged.render(), None, None, false);
+    write_checkpoint(options, &merged);
+}
+
+fn resume(camera: &Camera, world: &dyn Hittable, path: &str) -> RenderState {
+    let state = load_state(path);
+    if let Err(err) = camera.new_state(world).check_compatible(&state) {
+        eprintln!("error: checkpoint {path} does not match this render: {err}");
+        std::process::exit(2);
+    }
+    state
+}
+
+fn write_checkpoint(options: &Options, state: &RenderState) {
+    if let Some(path) = &options.checkpoint {
+        exit_on_error(
+            write_atomically(path, |out| checkpoint::save(out, state)),
+            "failed to write checkpoint",
+        );
+    }
+}
+
+// Intermediate snapshots skip denoising, which would otherwise take a large share of a time
+// budget while only the final image needs it.
+fn write_outputs(
+    options: &Options,
+    render: &Render,
+    crop: Option<&Rect>,
+    frame: Option<u32>,
+    snapshot: bool,
+) {
+    let cropped = crop.map(|crop| render.crop(crop));
+    let render = cropped.as_ref().unwrap_or(render);
+    let denoised = match (options.denoise, &render.aovs) {
+        (Some(denoiser), Some(aovs)) if !snapshot => Some(denoiser.apply(&render.beauty, aovs)),
+        _ => None,
+    };
+    let beauty = denoised.as_ref().unwrap_or(&render.beauty);
+    let output = match (&options.output, frame) {
+        (None, Some(_)) => Some("frame_####.ppm"),
+        (output, _) => output.as_deref(),
+    };
+    match output {
+        Some(path) => exit_on_error(
+            write_atomically(&Options::frame_path(path, frame), |out| {
+                beauty.write_ppm(out, &options.tone_mapper())
+            }),
+            "failed to write image",
+        ),
+        None => exit_on_error(
+            beauty.write_ppm(&mut io::stdout().lock(), &options.tone_mapper()),
+            "failed to write image",
+        ),
+    }
+    if let (Some(prefix), Some(aovs)) = (&options.aovs, &render.aovs) {
+        exit_on_error(
+            aovs.save(&Options::frame_path(prefix, frame)),
+            "failed to write AOVs",
+        );
+    }
+    if let Some(path) = &options.sample_heatmap {
+        let heatmap = sample_heatmap(&render.sample_counts, beauty.width, beauty.height);
+        exit_on_error(
+            write_atomically(&Options::frame_path(path, frame), |out| {
+                heatmap.write_ppm(out, &ToneMapper::default())
+            }),
+            "failed to write sample heatmap",
+        );
+    }
+}
+
+fn write_atomically(
+    path: &str,
+    write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+) -> io::Result<()> {
+    let partial = format!("{path}.partial");
+    let mut out = BufWriter::new(File::create(&partial)?);
+    write(&mut out)?;
+    out.into_inner()
+        .map_err(|err| err.into_error())?
+        .sync_all()?;
+    fs::rename(partial, path)
+}
+
+fn exit_on_error<T>(result: io::Result<T>, message: &str) -> T {
+    result.unwrap_or_else(|err| {
+        eprintln!("error: {message}: {err}");
+        std::process::exit(1);
+    })
 }
@@ -1,14 +1,54 @@
-use crate::{color::Color, hittable::HitRecord, ray::Ray, vec3::Vec3};
+use std::sync::Arc;
+
+use crate::{
+    color::Color,
+    fingerprint::Fingerprint,
+    hittable::HitRecord,
+    microfacet::{Frame, Ggx, fresnel_conductor, fresnel_dielectric},
+    ray::Ray,
+    sampler::Sampler,
+    texture::Texture,
+    vec3::Vec3,
+};
 
 pub struct Scatter {
     pub ray: Ray,
     pub attenuation: Color,
 }
 
-pub trait Material {
-    fn scatter(&self, _ray: &Ray, _hit: &HitRecord) -> Option<Scatter> {
+pub trait Material: Send + Sync {
+    fn scatter(&self, _ray: &Ray, _hit: &HitRecord, _sampler: &mut dyn Sampler) -> Option<Scatter> {
         None
     }
+
+    fn albedo(&self, _hit: &HitRecord) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    fn emitted(&self, _hit: &HitRecord) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint);
+}
+
+// Lets materials picked at runtime be used wherever a concrete material type is expected.
+impl Material for Arc<dyn Material> {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, sampler: &mut dyn Sampler) -> Option<Scatter> {
+        self.as_ref().scatter(ray, hit, sampler)
+    }
+
+    fn albedo(&self, hit: &HitRecord) -> Color {
+        self.as_ref().albedo(hit)
+    }
+
+    fn emitted(&self, hit: &HitRecord) -> Color {
+        self.as_ref().emitted(hit)
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        self.as_ref().fingerprint(fingerprint)
+    }
 }
 
 pub struct Lambertian {
@@ -22,19 +62,28 @@ impl Lambertian {
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _ray: &Ray, hit: &HitRecord) -> Option<Scatter> {
-        let scatter_direction = &hit.normal + &Vec3::random_unit();
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, sampler: &mut dyn Sampler) -> Option<Scatter> {
+        let scatter_direction = &hit.normal + &Vec3::unit_from_sample(sampler.get_2d());
         let scatter_direction = if scatter_direction.near_zero() {
             hit.normal.clone()
         } else {
             scatter_direction
         };
-        let scattered = Ray::new(hit.point.clone(), scatter_direction);
+        let scattered = Ray::with_time(hit.point.clone(), scatter_direction, ray.time);
         Some(Scatter {
             ray: scattered,
             attenuation: self.albedo.clone(),
         })
     }
+
+    fn albedo(&self, _hit: &HitRecord) -> Color {
+        self.albedo.clone()
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_str("Lambertian");
+        fingerprint.write_vec3(&self.albedo);
+    }
 }
 
 pub struct Metal {
@@ -52,10 +101,10 @@ impl Metal {
 }
 
 impl Material for Metal {
-    fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<Scatter> {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, sampler: &mut dyn Sampler) -> Option<Scatter> {
         let reflected = Vec3::reflect(&ray.direction, &hit.normal);
-        let reflected = reflected.unit() + (Vec3::random_unit() * self.fuzz);
-        let scattered = Ray::new(hit.point.clone(), reflected);
+        let reflected = reflected.unit() + (Vec3::unit_from_sample(sampler.get_2d()) * self.fuzz);
+        let scattered = Ray::with_time(hit.point.clone(), reflected, ray.time);
         if scattered.direction.dot(&hit.normal) > 0.0 {
             Some(Scatter {
                 ray: scattered,
@@ -65,15 +114,130 @@ impl Material for Metal {
             None
         }
     }
+
+    fn albedo(&self, _hit: &HitRecord) -> Color {
+        self.albedo.clone()
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_str("Metal");
+        fingerprint.write_vec3(&self.albedo);
+        fingerprint.write_f64(self.fuzz);
+    }
+}
+
+// Rough metal with a GGX microfacet distribution and the Fresnel reflectance of a complex
+// index of refraction, given per RGB channel.
+pub struct Conductor {
+    eta: Color,
+    k: Color,
+    roughness: (f64, f64),
+    distribution: Ggx,
+}
+
+impl Conductor {
+    pub fn new(eta: Color, k: Color, roughness_u: f64, roughness_v: f64) -> Self {
+        Self {
+            eta,
+            k,
+            roughness: (roughness_u, roughness_v),
+            distribution: Ggx::from_roughness(roughness_u, roughness_v),
+        }
+    }
+
+    // Complex indices of refraction of common metals at the wavelengths of the RGB primaries.
+    pub fn preset(name: &str) -> Option<(Color, Color)> {
+        match name {
+            "gold" => Some((
+                Color::new(0.143, 0.374, 1.442),
+                Color::new(3.983, 2.385, 1.603),
+            )),
+            "copper" => Some((
+                Color::new(0.200, 0.924, 1.102),
+                Color::new(3.912, 2.452, 2.142),
+            )),
+            "aluminum" => Some((
+                Color::new(1.657, 0.880, 0.521),
+                Color::new(9.224, 6.270, 4.837),
+            )),
+            "silver" => Some((
+                Color::new(0.155, 0.117, 0.138),
+                Color::new(4.828, 3.122, 2.147),
+            )),
+            _ => None,
+        }
+    }
+
+    fn fresnel(&self, cos_theta: f64) -> Color {
+        Color::new(
+            fresnel_conductor(cos_theta, self.eta.x, self.k.x),
+            fresnel_conductor(cos_theta, self.eta.y, self.k.y),
+            fresnel_conductor(cos_theta, self.eta.z, self.k.z),
+        )
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, sampler: &mut dyn Sampler) -> Option<Scatter> {
+        let frame = Frame::new(&hit.normal);
+        let wo = frame.to_local(&-ray.direction.unit());
+        if wo.z <= 0.0 {
+            return None;
+        }
+        let m = self
+            .distribution
+            .sample_visible_normal(&wo, sampler.get_2d());
+        let wi = Vec3::reflect(&-wo.clone(), &m);
+        if wi.z <= 0.0 {
+            return None;
+        }
+        let weight = self.distribution.g2(&wo, &wi) / self.distribution.g1(&wo);
+        Some(Scatter {
+            ray: Ray::with_time(hit.point.clone(), frame.to_world(&wi), ray.time),
+            attenuation: self.fresnel(wo.dot(&m)) * weight,
+        })
+    }
+
+    fn albedo(&self, _hit: &HitRecord) -> Color {
+        self.fresnel(1.0)
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_str("Conductor");
+        fingerprint.write_vec3(&self.eta);
+        fingerprint.write_vec3(&self.k);
+        fingerprint.write_f64(self.roughness.0);
+        fingerprint.write_f64(self.roughness.1);
+    }
 }
 
 pub struct Dielectric {
     refraction_index: f64,
+    absorption: Color,
 }
 
 impl Dielectric {
     pub fn new(refraction_index: f64) -> Self {
-        Self { refraction_index }
+        Self {
+            refraction_index,
+            absorption: Color::default(),
+        }
+    }
+
+    // Absorption coefficient per unit length inside the medium, for Beer-Lambert attenuation.
+    pub fn with_absorption(mut self, absorption: Color) -> Self {
+        self.absorption = absorption;
+        self
+    }
+
+    // Coefficient that tints light to `color` after travelling `distance` through the medium.
+    pub fn absorption_from_color(color: &Color, distance: f64) -> Color {
+        let coefficient = |transmittance: f64| -transmittance.clamp(1e-6, 1.0).ln() / distance;
+        Color::new(
+            coefficient(color.x),
+            coefficient(color.y),
+            coefficient(color.z),
+        )
     }
 
     pub fn reflectance(cosine: f64, refraction_index: f64) -> f64 {
@@ -84,8 +248,18 @@ impl Dielectric {
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<Scatter> {
-        let attenuation = Color::new(1.0, 1.0, 1.0);
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, sampler: &mut dyn Sampler) -> Option<Scatter> {
+        // A back-face hit ends a segment travelled inside the medium.
+        let attenuation = if hit.front_face {
+            Color::new(1.0, 1.0, 1.0)
+        } else {
+            let distance = hit.time * ray.direction.len();
+            Color::new(
+                (-self.absorption.x * distance).exp(),
+                (-self.absorption.y * distance).exp(),
+                (-self.absorption.z * distance).exp(),
+            )
+        };
         let ri = if hit.front_face {
             1.0 / self.refraction_index
         } else {
@@ -97,7 +271,7 @@ impl Material for Dielectric {
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
 
         let cannot_refrect =
-            ri * sin_theta > 1.0 || Dielectric::reflectance(cos_theta, ri) > rand::random();
+            ri * sin_theta > 1.0 || Dielectric::reflectance(cos_theta, ri) > sampler.get_1d();
 
         let direction = if cannot_refrect {
             Vec3::reflect(&unit_direction, &hit.normal)
@@ -106,8 +280,257 @@ impl Material for Dielectric {
         };
 
         Some(Scatter {
-            ray: Ray::new(hit.point.clone(), direction),
+            ray: Ray::with_time(hit.point.clone(), direction, ray.time),
             attenuation,
         })
     }
+
+    fn albedo(&self, _hit: &HitRecord) -> Color {
+        Color::new(1.0, 1.0, 1.0)
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_str("Dielectric");
+        fingerprint.write_f64(self.refraction_index);
+        if self.absorption.len_squared() > 0.0 {
+            fingerprint.write_vec3(&self.absorption);
+        }
+    }
+}
+
+// Frosted glass: a dielectric interface whose microfacet normals follow a GGX distribution.
+// Each scatter picks reflection or transmission through a visible microfacet with the exact
+// Fresnel probability, leaving a G2 / G1 weight.
+pub struct RoughDielectric {
+    refraction_index: f64,
+    roughness: f64,
+    distribution: Ggx,
+}
+
+impl RoughDielectric {
+    pub fn new(refraction_index: f64, roughness: f64) -> Self {
+        Self {
+            refraction_index,
+            roughness,
+            distribution: Ggx::from_roughness(roughness, roughness),
+        }
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, sampler: &mut dyn Sampler) -> Option<Scatter> {
+        let ri = if hit.front_face {
+            1.0 / self.refraction_index
+        } else {
+            self.refraction_index
+        };
+
+        let frame = Frame::new(&hit.normal);
+        let wo = frame.to_local(&-ray.direction.unit());
+        if wo.z <= 0.0 {
+            return None;
+        }
+        let m = self
+            .distribution
+            .sample_visible_normal(&wo, sampler.get_2d());
+        let reflectance = fresnel_dielectric(wo.dot(&m), 1.0 / ri);
+
+        let wi = if reflectance > sampler.get_1d() {
+            let wi = Vec3::reflect(&-wo.clone(), &m);
+            if wi.z <= 0.0 {
+                return None;
+            }
+            wi
+        } else {
+            let wi = Vec3::refract(&-wo.clone(), &m, ri);
+            if wi.z >= 0.0 {
+                return None;
+            }
+            wi
+        };
+
+        let weight = self.distribution.g2(&wo, &wi) / self.distribution.g1(&wo);
+        Some(Scatter {
+            ray: Ray::with_time(hit.point.clone(), frame.to_world(&wi), ray.time),
+            attenuation: Color::new(weight, weight, weight),
+        })
+    }
+
+    fn albedo(&self, _hit: &HitRecord) -> Color {
+        Color::new(1.0, 1.0, 1.0)
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_str("RoughDielectric");
+        fingerprint.write_f64(self.refraction_index);
+        fingerprint.write_f64(self.roughness);
+    }
+}
+
+// Principled material following the Disney/glTF parameterization. A single scatter picks one
+// lobe stochastically: the clearcoat, then metal or a dielectric base whose Fresnel-weighted
+// specular reflection sits over either transmission or sheened diffuse.
+pub struct Principled {
+    pub base_color: Arc<dyn Texture>,
+    pub metallic: Arc<dyn Texture>,
+    pub roughness: Arc<dyn Texture>,
+    pub specular: Arc<dyn Texture>,
+    pub specular_tint: Arc<dyn Texture>,
+    pub sheen: Arc<dyn Texture>,
+    pub clearcoat: Arc<dyn Texture>,
+    pub transmission: Arc<dyn Texture>,
+    pub emission: Arc<dyn Texture>,
+    pub ior: f64,
+}
+
+impl Principled {
+    const CLEARCOAT_ROUGHNESS: f64 = 0.1;
+
+    fn scalar(texture: &Arc<dyn Texture>, hit: &HitRecord) -> f64 {
+        texture.value(hit.u, hit.v).x.clamp(0.0, 1.0)
+    }
+
+    fn lerp(a: Color, b: Color, t: f64) -> Color {
+        a * (1.0 - t) + b * t
+    }
+
+    fn schlick(f0: Color, cos_theta: f64) -> Color {
+        let weight = (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5);
+        Self::lerp(f0, Color::new(1.0, 1.0, 1.0), weight)
+    }
+
+    fn reflect(
+        ray: &Ray,
+        hit: &HitRecord,
+        frame: &Frame,
+        distribution: &Ggx,
+        wo: &Vec3,
+        m: &Vec3,
+        attenuation: Color,
+    ) -> Option<Scatter> {
+        let wi = Vec3::reflect(&-wo.clone(), m);
+        if wi.z <= 0.0 {
+            return None;
+        }
+        let weight = distribution.g2(wo, &wi) / distribution.g1(wo);
+        Some(Scatter {
+            ray: Ray::with_time(hit.point.clone(), frame.to_world(&wi), ray.time),
+            attenuation: attenuation * weight,
+        })
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, sampler: &mut dyn Sampler) -> Option<Scatter> {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let base_color = self.base_color.value(hit.u, hit.v);
+        let frame = Frame::new(&hit.normal);
+        let wo = frame.to_local(&-ray.direction.unit());
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        let clearcoat = Self::scalar(&self.clearcoat, hit);
+        if clearcoat > 0.0 {
+            let distribution =
+                Ggx::from_roughness(Self::CLEARCOAT_ROUGHNESS, Self::CLEARCOAT_ROUGHNESS);
+            let m = distribution.sample_visible_normal(&wo, sampler.get_2d());
+            if clearcoat * fresnel_dielectric(wo.dot(&m), 1.5) > sampler.get_1d() {
+                return Self::reflect(ray, hit, &frame, &distribution, &wo, &m, white);
+            }
+        }
+
+        let roughness = Self::scalar(&self.roughness, hit);
+        let distribution = Ggx::from_roughness(roughness, roughness);
+        let m = distribution.sample_visible_normal(&wo, sampler.get_2d());
+        let cos_theta = wo.dot(&m);
+
+        if Self::scalar(&self.metallic, hit) > sampler.get_1d() {
+            let fresnel = Self::schlick(base_color, cos_theta);
+            return Self::reflect(ray, hit, &frame, &distribution, &wo, &m, fresnel);
+        }
+
+        let luminance = base_color.luminance();
+        let tint = if luminance > 0.0 {
+            base_color.clone() * (1.0 / luminance)
+        } else {
+            white.clone()
+        };
+        let specular_color =
+            Self::lerp(white.clone(), tint, Self::scalar(&self.specular_tint, hit))
+                * (0.08 * Self::scalar(&self.specular, hit));
+
+        let eta = if hit.front_face {
+            self.ior
+        } else {
+            1.0 / self.ior
+        };
+        let transmission = Self::scalar(&self.transmission, hit);
+        let dielectric = fresnel_dielectric(cos_theta, eta);
+        let fresnel = Self::lerp(
+            Self::schlick(specular_color, cos_theta),
+            Color::new(dielectric, dielectric, dielectric),
+            transmission,
+        );
+
+        let probability = fresnel.luminance().clamp(0.0, 1.0);
+        if probability > sampler.get_1d() {
+            let attenuation = fresnel * (1.0 / probability);
+            return Self::reflect(ray, hit, &frame, &distribution, &wo, &m, attenuation);
+        }
+        let remaining = Color::new(1.0 - fresnel.x, 1.0 - fresnel.y, 1.0 - fresnel.z)
+            * (1.0 / (1.0 - probability));
+
+        if transmission > sampler.get_1d() {
+            let wi = Vec3::refract(&-wo.clone(), &m, 1.0 / eta);
+            if wi.z >= 0.0 {
+                return None;
+            }
+            let weight = distribution.g2(&wo, &wi) / distribution.g1(&wo);
+            return Some(Scatter {
+                ray: Ray::with_time(hit.point.clone(), frame.to_world(&wi), ray.time),
+                attenuation: remaining * base_color * weight,
+            });
+        }
+
+        let direction = &hit.normal + &Vec3::unit_from_sample(sampler.get_2d());
+        let direction = if direction.near_zero() {
+            hit.normal.clone()
+        } else {
+            direction
+        };
+        // Sheen adds a white retro-reflective rim that grows towards grazing angles.
+        let half = (-ray.direction.unit() + direction.unit()).unit();
+        let sheen = Self::scalar(&self.sheen, hit) * (1.0 - direction.unit().dot(&half)).powi(5);
+        Some(Scatter {
+            ray: Ray::with_time(hit.point.clone(), direction, ray.time),
+            attenuation: remaining * (base_color + white * sheen),
+        })
+    }
+
+    fn albedo(&self, hit: &HitRecord) -> Color {
+        self.base_color.value(hit.u, hit.v)
+    }
+
+    fn emitted(&self, hit: &HitRecord) -> Color {
+        self.emission.value(hit.u, hit.v)
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_str("Principled");
+        for texture in [
+            &self.base_color,
+            &self.metallic,
+            &self.roughness,
+            &self.specular,
+            &self.specular_tint,
+            &self.sheen,
+            &self.clearcoat,
+            &self.transmission,
+            &self.emission,
+        ] {
+            texture.fingerprint(fingerprint);
+        }
+        fingerprint.write_f64(self.ior);
+    }
 }
@@ -0,0 +1,129 @@
+use std::f64::consts::PI;
+
+use crate::vec3::Vec3;
+
+// Orthonormal shading frame with the surface normal as its z axis. The tangent follows the
+// world's y axis around the normal so anisotropic highlights keep a stable orientation.
+pub struct Frame {
+    pub tangent: Vec3,
+    pub bitangent: Vec3,
+    pub normal: Vec3,
+}
+
+impl Frame {
+    pub fn new(normal: &Vec3) -> Self {
+        let normal = normal.unit();
+        let up = if normal.y.abs() < 0.999 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let tangent = up.cross(&normal).unit();
+        let bitangent = normal.cross(&tangent);
+        Self {
+            tangent,
+            bitangent,
+            normal,
+        }
+    }
+
+    pub fn to_local(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            v.dot(&self.tangent),
+            v.dot(&self.bitangent),
+            v.dot(&self.normal),
+        )
+    }
+
+    pub fn to_world(&self, v: &Vec3) -> Vec3 {
+        &self.tangent * v.x + &self.bitangent * v.y + &self.normal * v.z
+    }
+}
+
+// Trowbridge-Reitz (GGX) microfacet distribution with Smith masking, in the local shading frame.
+#[derive(Clone, Copy)]
+pub struct Ggx {
+    pub alpha_x: f64,
+    pub alpha_y: f64,
+}
+
+impl Ggx {
+    // Perceptual roughness in [0, 1] maps to alpha = roughness^2, clamped away from a perfect
+    // mirror where the distribution degenerates.
+    pub fn from_roughness(roughness_x: f64, roughness_y: f64) -> Self {
+        let alpha = |roughness: f64| (roughness * roughness).clamp(1e-4, 1.0);
+        Self {
+            alpha_x: alpha(roughness_x),
+            alpha_y: alpha(roughness_y),
+        }
+    }
+
+    pub fn lambda(&self, w: &Vec3) -> f64 {
+        if w.z == 0.0 {
+            return f64::INFINITY;
+        }
+        let (x, y) = (w.x * self.alpha_x, w.y * self.alpha_y);
+        let tan2 = (x * x + y * y) / (w.z * w.z);
+        ((1.0 + tan2).sqrt() - 1.0) / 2.0
+    }
+
+    pub fn g1(&self, w: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    // Height-correlated masking-shadowing.
+    pub fn g2(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    // Samples a microfacet normal visible from `wo` (Heitz 2018), so that reflecting about it
+    // has weight F * G2 / G1 and no rays are wasted on back-facing microfacets.
+    pub fn sample_visible_normal(&self, wo: &Vec3, (u1, u2): (f64, f64)) -> Vec3 {
+        let vh = Vec3::new(self.alpha_x * wo.x, self.alpha_y * wo.y, wo.z).unit();
+        let len2 = vh.x * vh.x + vh.y * vh.y;
+        let t1 = if len2 > 0.0 {
+            Vec3::new(-vh.y, vh.x, 0.0) / len2.sqrt()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = vh.cross(&t1);
+
+        let r = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z);
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * r * phi.sin();
+        let nh = &t1 * p1 + &t2 * p2 + &vh * (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+        Vec3::new(self.alpha_x * nh.x, self.alpha_y * nh.y, nh.z.max(1e-6)).unit()
+    }
+}
+
+// Unpolarized Fresnel reflectance of a conductor with complex index of refraction eta + ik.
+pub fn fresnel_conductor(cos_theta: f64, eta: f64, k: f64) -> f64 {
+    let cos2 = cos_theta * cos_theta;
+    let sin2 = 1.0 - cos2;
+    let t0 = eta * eta - k * k - sin2;
+    let a2_plus_b2 = (t0 * t0 + 4.0 * eta * eta * k * k).sqrt();
+    let t1 = a2_plus_b2 + cos2;
+    let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+    let t2 = 2.0 * cos_theta * a;
+    let rs = (t1 - t2) / (t1 + t2);
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+    0.5 * (rp + rs)
+}
+
+// Unpolarized Fresnel reflectance of a dielectric interface, where `eta` is the ratio of the
+// transmitted to the incident index of refraction. Returns 1 under total internal reflection.
+pub fn fresnel_dielectric(cos_theta: f64, eta: f64) -> f64 {
+    let cos_i = cos_theta.clamp(0.0, 1.0);
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let rs = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    let rp = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    0.5 * (rs * rs + rp * rp)
+}
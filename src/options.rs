@@ -0,0 +1,731 @@
+use std::{io, sync::Arc, time::Duration};
+
+use crate::{
+    adaptive::AdaptiveSampling,
+    aperture::ApertureKind,
+    color::Color,
+    denoise::Denoiser,
+    filter::{Filter, FilterKind},
+    image::Rect,
+    integrator::{
+        Albedo, AmbientOcclusion, BounceHeatmap, Depth, Integrator, Normals, PathTracer, Uv,
+    },
+    lens::Lens,
+    lens_system::LensSystem,
+    material::{Conductor, Dielectric, Lambertian, Material, Metal, Principled, RoughDielectric},
+    physical::PhysicalCamera,
+    progressive::Progressive,
+    projection::Projection,
+    sampler::SamplerKind,
+    stereo::{Convergence, Stereo, StereoLayout},
+    texture::TextureKind,
+    tonemap::{ToneMap, ToneMapper},
+};
+
+#[derive(Clone, Copy, Default)]
+pub enum IntegratorKind {
+    #[default]
+    Path,
+    Normals,
+    Depth,
+    Albedo,
+    Uv,
+    AmbientOcclusion,
+    Bounces,
+}
+
+impl IntegratorKind {
+    pub fn build(&self) -> Arc<dyn Integrator> {
+        match self {
+            IntegratorKind::Path => Arc::new(PathTracer),
+            IntegratorKind::Normals => Arc::new(Normals),
+            IntegratorKind::Depth => Arc::new(Depth::default()),
+            IntegratorKind::Albedo => Arc::new(Albedo),
+            IntegratorKind::Uv => Arc::new(Uv),
+            IntegratorKind::AmbientOcclusion => Arc::new(AmbientOcclusion::default()),
+            IntegratorKind::Bounces => Arc::new(BounceHeatmap),
+        }
+    }
+}
+
+impl std::str::FromStr for IntegratorKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "path" => Ok(IntegratorKind::Path),
+            "normals" => Ok(IntegratorKind::Normals),
+            "depth" => Ok(IntegratorKind::Depth),
+            "albedo" => Ok(IntegratorKind::Albedo),
+            "uv" => Ok(IntegratorKind::Uv),
+            "ao" => Ok(IntegratorKind::AmbientOcclusion),
+            "bounces" => Ok(IntegratorKind::Bounces),
+            _ => Err(format!(
+                "unknown integrator '{s}' (expected path, normals, depth, albedo, uv, ao or bounces)"
+            )),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct PrincipledKind {
+    pub base_color: TextureKind,
+    pub metallic: TextureKind,
+    pub roughness: TextureKind,
+    pub specular: TextureKind,
+    pub specular_tint: TextureKind,
+    pub sheen: TextureKind,
+    pub clearcoat: TextureKind,
+    pub transmission: TextureKind,
+    pub emission: TextureKind,
+    pub ior: f64,
+}
+
+impl Default for PrincipledKind {
+    fn default() -> Self {
+        let constant = |value| TextureKind::Constant(Color::new(value, value, value));
+        Self {
+            base_color: constant(0.8),
+            metallic: constant(0.0),
+            roughness: constant(0.5),
+            specular: constant(0.5),
+            specular_tint: constant(0.0),
+            sheen: constant(0.0),
+            clearcoat: constant(0.0),
+            transmission: constant(0.0),
+            emission: constant(0.0),
+            ior: 1.45,
+        }
+    }
+}
+
+impl PrincipledKind {
+    fn build(&self) -> io::Result<Principled> {
+        Ok(Principled {
+            base_color: self.base_color.build(true)?,
+            metallic: self.metallic.build(false)?,
+            roughness: self.roughness.build(false)?,
+            specular: self.specular.build(false)?,
+            specular_tint: self.specular_tint.build(false)?,
+            sheen: self.sheen.build(false)?,
+            clearcoat: self.clearcoat.build(false)?,
+            transmission: self.transmission.build(false)?,
+            emission: self.emission.build(true)?,
+            ior: self.ior,
+        })
+    }
+}
+
+impl std::str::FromStr for PrincipledKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut principled = PrincipledKind::default();
+        for parameter in s.split(':').filter(|parameter| !parameter.is_empty()) {
+            let (key, value) = parameter
+                .split_once('=')
+                .ok_or_else(|| format!("invalid parameter '{parameter}' (expected key=value)"))?;
+            let texture = match key {
+                "base_color" => &mut principled.base_color,
+                "metallic" => &mut principled.metallic,
+                "roughness" => &mut principled.roughness,
+                "specular" => &mut principled.specular,
+                "specular_tint" => &mut principled.specular_tint,
+                "sheen" => &mut principled.sheen,
+                "clearcoat" => &mut principled.clearcoat,
+                "transmission" => &mut principled.transmission,
+                "emission" => &mut principled.emission,
+                "ior" => {
+                    principled.ior = MaterialKind::number(value)?;
+                    continue;
+                }
+                _ => return Err(format!("unknown principled parameter '{key}'")),
+            };
+            *texture = value.parse()?;
+        }
+        Ok(principled)
+    }
+}
+
+#[derive(Clone)]
+pub enum MaterialKind {
+    Lambertian(Color),
+    Metal(Color, f64),
+    Dielectric(f64, Color),
+    RoughDielectric(f64, f64),
+    Conductor {
+        eta: Color,
+        k: Color,
+        roughness_u: f64,
+        roughness_v: f64,
+    },
+    Principled(Box<PrincipledKind>),
+}
+
+impl MaterialKind {
+    pub fn build(&self) -> io::Result<Arc<dyn Material>> {
+        Ok(match self {
+            MaterialKind::Lambertian(albedo) => Arc::new(Lambertian::new(albedo.clone())),
+            MaterialKind::Metal(albedo, fuzz) => Arc::new(Metal::new(albedo.clone(), *fuzz)),
+            MaterialKind::Dielectric(ior, absorption) => {
+                Arc::new(Dielectric::new(*ior).with_absorption(absorption.clone()))
+            }
+            MaterialKind::RoughDielectric(ior, roughness) => {
+                Arc::new(RoughDielectric::new(*ior, *roughness))
+            }
+            MaterialKind::Conductor {
+                eta,
+                k,
+                roughness_u,
+                roughness_v,
+            } => Arc::new(Conductor::new(
+                eta.clone(),
+                k.clone(),
+                *roughness_u,
+                *roughness_v,
+            )),
+            MaterialKind::Principled(principled) => Arc::new(principled.build()?),
+        })
+    }
+
+    fn number(value: &str) -> Result<f64, String> {
+        value
+            .parse()
+            .map_err(|err| format!("invalid number '{value}': {err}"))
+    }
+
+    fn color(value: &str) -> Result<Color, String> {
+        match value.split(',').collect::<Vec<_>>()[..] {
+            [r, g, b] => Ok(Color::new(
+                Self::number(r)?,
+                Self::number(g)?,
+                Self::number(b)?,
+            )),
+            _ => Err(format!("invalid color '{value}' (expected r,g,b)")),
+        }
+    }
+}
+
+impl std::str::FromStr for MaterialKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split(':').collect();
+        match fields[..] {
+            ["lambertian", albedo] => Ok(MaterialKind::Lambertian(Self::color(albedo)?)),
+            ["metal", albedo] => Ok(MaterialKind::Metal(Self::color(albedo)?, 0.0)),
+            ["metal", albedo, fuzz] => Ok(MaterialKind::Metal(
+                Self::color(albedo)?,
+                Self::number(fuzz)?,
+            )),
+            ["dielectric", ior] => Ok(MaterialKind::Dielectric(
+                Self::number(ior)?,
+                Color::default(),
+            )),
+            // The absorption is given as the color light takes on after travelling `distance`
+            // through the medium.
+            ["dielectric", ior, color, ref distance @ ..] if distance.len() <= 1 => {
+                let distance = match distance.first() {
+                    Some(distance) => Self::number(distance)?,
+                    None => 1.0,
+                };
+                if distance <= 0.0 {
+                    return Err(format!("invalid absorption distance in '{s}'"));
+                }
+                let color = Self::color(color)?;
+                if [color.x, color.y, color.z]
+                    .iter()
+                    .any(|component| !(*component > 0.0 && *component <= 1.0))
+                {
+                    return Err(format!(
+                        "invalid absorption color in '{s}' (components must be in (0, 1])"
+                    ));
+                }
+                Ok(MaterialKind::Dielectric(
+                    Self::number(ior)?,
+                    Dielectric::absorption_from_color(&color, distance),
+                ))
+            }
+            ["rough-dielectric", ior, roughness] => Ok(MaterialKind::RoughDielectric(
+                Self::number(ior)?,
+                Self::number(roughness)?,
+            )),
+            ["conductor", preset, ref roughness @ ..] if roughness.len() <= 2 => {
+                let (eta, k) = Conductor::preset(preset).ok_or_else(|| {
+                    format!(
+                        "unknown conductor '{preset}' (expected gold, copper, aluminum or silver)"
+                    )
+                })?;
+                let roughness_u = match roughness.first() {
+                    Some(roughness) => Self::number(roughness)?,
+                    None => 0.0,
+                };
+                let roughness_v = match roughness.get(1) {
+                    Some(roughness) => Self::number(roughness)?,
+                    None => roughness_u,
+                };
+                Ok(MaterialKind::Conductor {
+                    eta,
+                    k,
+                    roughness_u,
+                    roughness_v,
+                })
+            }
+            ["principled", ..] => Ok(MaterialKind::Principled(Box::new(
+                s["principled".len()..].parse()?,
+            ))),
+            _ => Err(format!(
+                "invalid material '{s}' (expected lambertian:r,g,b, metal:r,g,b[:fuzz], dielectric:ior[:r,g,b[:distance]], rough-dielectric:ior:roughness, conductor:preset[:roughness[:roughness_v]] or principled[:key=value...])"
+            )),
+        }
+    }
+}
+
+pub struct Options {
+    pub width: i32,
+    pub samples_per_pixel: i32,
+    pub integrator: IntegratorKind,
+    pub projection: Projection,
+    pub physical: Option<PhysicalCamera>,
+    pub aperture: ApertureKind,
+    pub lens: Lens,
+    pub lens_system: Option<String>,
+    pub rolling_shutter: Option<f64>,
+    pub stereo: Option<StereoLayout>,
+    pub interocular: f64,
+    pub convergence: Convergence,
+    pub convergence_distance: f64,
+    pub aovs: Option<String>,
+    pub denoise: Option<Denoiser>,
+    pub exposure: f64,
+    pub tonemap: ToneMap,
+    pub adaptive_threshold: Option<f64>,
+    pub min_spp: Option<i32>,
+    pub max_spp: Option<i32>,
+    pub sample_heatmap: Option<String>,
+    pub sampler: SamplerKind,
+    pub seed: u64,
+    pub filter: FilterKind,
+    pub filter_radius: Option<f64>,
+    pub output: Option<String>,
+    pub pass_spp: Option<i32>,
+    pub snapshot_interval: Option<f64>,
+    pub time_budget: Option<f64>,
+    pub checkpoint: Option<String>,
+    pub resume: Option<String>,
+    pub crop: Option<Rect>,
+    pub crop_full_frame: bool,
+    pub workers: Option<usize>,
+    pub tile_size: usize,
+    pub worker: bool,
+    pub merge: Option<Vec<String>>,
+    pub animation: Option<String>,
+    pub frames: Option<(u32, u32)>,
+    pub materials: Vec<(usize, MaterialKind)>,
+}
+
+impl Options {
+    pub fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options {
+            width: 800,
+            samples_per_pixel: 800,
+            integrator: IntegratorKind::default(),
+            projection: Projection::default(),
+            physical: None,
+            aperture: ApertureKind::default(),
+            lens: Lens::default(),
+            lens_system: None,
+            rolling_shutter: None,
+            stereo: None,
+            interocular: 0.064,
+            convergence: Convergence::default(),
+            convergence_distance: 1.0,
+            aovs: None,
+            denoise: None,
+            exposure: 0.0,
+            tonemap: ToneMap::default(),
+            adaptive_threshold: None,
+            min_spp: None,
+            max_spp: None,
+            sample_heatmap: None,
+            sampler: SamplerKind::default(),
+            seed: 0,
+            filter: FilterKind::default(),
+            filter_radius: None,
+            output: None,
+            pass_spp: None,
+            snapshot_interval: None,
+            time_budget: None,
+            checkpoint: None,
+            resume: None,
+            crop: None,
+            crop_full_frame: false,
+            workers: None,
+            tile_size: 32,
+            worker: false,
+            merge: None,
+            animation: None,
+            frames: None,
+            materials: Vec::new(),
+        };
+        let mut args = args.peekable();
+        if args.next_if(|arg| arg == "merge").is_some() {
+            options.merge = Some(Vec::new());
+        }
+        let mut flags = Vec::new();
+        while let Some(arg) = args.next() {
+            flags.push(arg.clone());
+            match arg.as_str() {
+                "--width" => options.width = Self::value(&arg, args.next())?,
+                "--spp" => options.samples_per_pixel = Self::value(&arg, args.next())?,
+                "--integrator" => options.integrator = Self::value(&arg, args.next())?,
+                "--projection" => options.projection = Self::value(&arg, args.next())?,
+                "--sensor" => {
+                    let sensor: String = Self::value(&arg, args.next())?;
+                    let (width, height) = sensor
+                        .split_once('x')
+                        .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+                        .ok_or_else(|| {
+                            format!("invalid value '{sensor}' for --sensor (expected WIDTHxHEIGHT in mm)")
+                        })?;
+                    let physical = options.physical.get_or_insert_with(PhysicalCamera::default);
+                    physical.sensor_width = width;
+                    physical.sensor_height = height;
+                }
+                "--focal-length" => {
+                    options
+                        .physical
+                        .get_or_insert_with(PhysicalCamera::default)
+                        .focal_length = Self::value(&arg, args.next())?
+                }
+                "--f-stop" => {
+                    options
+                        .physical
+                        .get_or_insert_with(PhysicalCamera::default)
+                        .f_number = Self::value(&arg, args.next())?
+                }
+                "--shutter" => {
+                    options
+                        .physical
+                        .get_or_insert_with(PhysicalCamera::default)
+                        .shutter = Self::seconds(&arg, args.next())?
+                }
+                "--rolling-shutter" => {
+                    options.rolling_shutter = Some(Self::seconds(&arg, args.next())?)
+                }
+                "--iso" => {
+                    options
+                        .physical
+                        .get_or_insert_with(PhysicalCamera::default)
+                        .iso = Self::value(&arg, args.next())?
+                }
+                "--focus-distance" => {
+                    options
+                        .physical
+                        .get_or_insert_with(PhysicalCamera::default)
+                        .focus_distance = Self::value(&arg, args.next())?
+                }
+                "--aperture" => options.aperture = Self::value(&arg, args.next())?,
+                "--distortion" => {
+                    (options.lens.k1, options.lens.k2) = Self::pair(&arg, args.next())?
+                }
+                "--chromatic-aberration" => {
+                    options.lens.chromatic_aberration = Self::value(&arg, args.next())?
+                }
+                "--lens-shift" => options.lens.shift = Self::pair(&arg, args.next())?,
+                "--lens-tilt" => options.lens.tilt = Self::value(&arg, args.next())?,
+                "--lens-system" => options.lens_system = Some(Self::value(&arg, args.next())?),
+                "--stereo" => options.stereo = Some(Self::value(&arg, args.next())?),
+                "--interocular" => options.interocular = Self::value(&arg, args.next())?,
+                "--convergence" => options.convergence = Self::value(&arg, args.next())?,
+                "--convergence-distance" => {
+                    options.convergence_distance = Self::value(&arg, args.next())?
+                }
+                "--aovs" => options.aovs = Some(Self::value(&arg, args.next())?),
+                "--denoise" => options.denoise = Some(Self::value(&arg, args.next())?),
+                "--exposure" => options.exposure = Self::value(&arg, args.next())?,
+                "--tonemap" => options.tonemap = Self::value(&arg, args.next())?,
+                "--adaptive-threshold" => {
+                    options.adaptive_threshold = Some(Self::value(&arg, args.next())?)
+                }
+                "--min-spp" => options.min_spp = Some(Self::value(&arg, args.next())?),
+                "--max-spp" => options.max_spp = Some(Self::value(&arg, args.next())?),
+                "--sampler" => options.sampler = Self::value(&arg, args.next())?,
+                "--seed" => options.seed = Self::value(&arg, args.next())?,
+                "-o" | "--output" => options.output = Some(Self::value(&arg, args.next())?),
+                "--pass-spp" => options.pass_spp = Some(Self::value(&arg, args.next())?),
+                "--snapshot-interval" => {
+                    options.snapshot_interval = Some(Self::value(&arg, args.next())?)
+                }
+                "--time-budget" => options.time_budget = Some(Self::value(&arg, args.next())?),
+                "--checkpoint" => options.checkpoint = Some(Self::value(&arg, args.next())?),
+                "--resume" => options.resume = Some(Self::value(&arg, args.next())?),
+                "--crop" => options.crop = Some(Self::value(&arg, args.next())?),
+                "--crop-output" => {
+                    options.crop_full_frame = match Self::value::<String>(&arg, args.next())?
+                        .as_str()
+                    {
+                        "cropped" => false,
+                        "full" => true,
+                        other => {
+                            return Err(format!(
+                                "invalid value '{other}' for --crop-output (expected cropped or full)"
+                            ));
+                        }
+                    }
+                }
+                "--workers" => options.workers = Some(Self::value(&arg, args.next())?),
+                "--tile-size" => options.tile_size = Self::value(&arg, args.next())?,
+                "--worker" => options.worker = true,
+                "--material" => {
+                    let value: String = Self::value(&arg, args.next())?;
+                    let (index, material) = value.split_once('=').ok_or_else(|| {
+                        format!("invalid value '{value}' for --material (expected INDEX=MATERIAL)")
+                    })?;
+                    let index = index
+                        .parse()
+                        .map_err(|_| format!("invalid object index '{index}' for --material"))?;
+                    let material = material
+                        .parse()
+                        .map_err(|err| format!("invalid value '{value}' for --material: {err}"))?;
+                    options.materials.push((index, material));
+                }
+                "--animation" => options.animation = Some(Self::value(&arg, args.next())?),
+                "--frames" => {
+                    let frames: String = Self::value(&arg, args.next())?;
+                    let (first, last) = frames.split_once('-').unwrap_or((&frames, &frames));
+                    options.frames = match (first.parse(), last.parse()) {
+                        (Ok(first), Ok(last)) if first <= last => Some((first, last)),
+                        _ => {
+                            return Err(format!(
+                                "invalid value '{frames}' for --frames (expected FIRST-LAST or FRAME)"
+                            ));
+                        }
+                    };
+                }
+                "--filter" => options.filter = Self::value(&arg, args.next())?,
+                "--filter-radius" => options.filter_radius = Some(Self::value(&arg, args.next())?),
+                "--sample-heatmap" => {
+                    options.sample_heatmap = Some(Self::value(&arg, args.next())?)
+                }
+                _ if !arg.starts_with('-') && options.merge.is_some() => {
+                    options.merge.as_mut().unwrap().push(arg)
+                }
+                _ => return Err(format!("unknown argument '{arg}'")),
+            }
+        }
+        if options.workers.is_some() && (options.resume.is_some() || options.time_budget.is_some())
+        {
+            return Err("--workers cannot be combined with --resume or --time-budget".to_string());
+        }
+        if options.width <= 0 {
+            return Err("--width must be positive".to_string());
+        }
+        if options.samples_per_pixel <= 0 || options.max_spp.is_some_and(|spp| spp <= 0) {
+            return Err("--spp and --max-spp must be positive".to_string());
+        }
+        for (flag, value) in [
+            ("--snapshot-interval", options.snapshot_interval),
+            ("--time-budget", options.time_budget),
+        ] {
+            if value.is_some_and(|seconds| !(seconds.is_finite() && seconds > 0.0)) {
+                return Err(format!("{flag} must be a positive number of seconds"));
+            }
+        }
+        // Narrower filters would miss samples that land between pixel centers.
+        if options
+            .filter_radius
+            .is_some_and(|radius| !(radius.is_finite() && radius >= 0.5))
+        {
+            return Err("--filter-radius must be a finite number of at least 0.5".to_string());
+        }
+        if options.merge.as_ref().is_some_and(Vec::is_empty) {
+            return Err("merge requires at least one checkpoint file".to_string());
+        }
+        if let Some(physical) = &options.physical {
+            let values = [
+                physical.sensor_width,
+                physical.sensor_height,
+                physical.focal_length,
+                physical.f_number,
+                physical.shutter,
+                physical.iso,
+                physical.focus_distance,
+            ];
+            if values.iter().any(|value| *value <= 0.0) {
+                return Err("physical camera parameters must be positive".to_string());
+            }
+        }
+        if options.animation.is_some()
+            && (options.workers.is_some()
+                || options.resume.is_some()
+                || options.checkpoint.is_some())
+        {
+            return Err(
+                "--animation cannot be combined with --workers, --resume or --checkpoint"
+                    .to_string(),
+            );
+        }
+        // The lens system traces its own rays from the film, bypassing the projection, stereo
+        // and thin-lens camera models.
+        if options.lens_system.is_some() {
+            let ignored = [
+                "--projection",
+                "--stereo",
+                "--aperture",
+                "--f-stop",
+                "--distortion",
+                "--chromatic-aberration",
+                "--lens-shift",
+                "--lens-tilt",
+            ];
+            if let Some(flag) = flags.iter().find(|flag| ignored.contains(&flag.as_str())) {
+                return Err(format!("--lens-system cannot be combined with {flag}"));
+            }
+        }
+        if options.frames.is_some() && options.animation.is_none() {
+            return Err("--frames requires --animation".to_string());
+        }
+        if options.rolling_shutter.is_some_and(|readout| readout < 0.0) {
+            return Err("--rolling-shutter must not be negative".to_string());
+        }
+        if options.convergence_distance <= 0.0 {
+            return Err("--convergence-distance must be positive".to_string());
+        }
+        Ok(options)
+    }
+
+    pub fn adaptive_sampling(&self) -> Option<AdaptiveSampling> {
+        self.adaptive_threshold.map(|threshold| {
+            AdaptiveSampling::new(
+                self.min_spp.unwrap_or(16),
+                self.max_spp.unwrap_or(self.samples_per_pixel),
+                threshold,
+            )
+        })
+    }
+
+    pub fn filter(&self) -> Filter {
+        Filter::new(self.filter, self.filter_radius)
+    }
+
+    // Checkpointed and time-budgeted renders run in small passes. Without an explicit interval
+    // they snapshot periodically, so checkpoints still save progress before the render finishes
+    // and snapshots don't eat into the time budget after every pass.
+    const SNAPSHOT_INTERVAL: f64 = 60.0;
+
+    pub fn progressive(&self) -> Progressive {
+        let snapshot_interval = match self.snapshot_interval {
+            None if self.checkpoint.is_some() || self.time_budget.is_some() => {
+                Some(Self::SNAPSHOT_INTERVAL)
+            }
+            interval => interval,
+        };
+        Progressive {
+            pass_samples: self.pass_spp,
+            snapshot_interval: snapshot_interval.map(Duration::from_secs_f64),
+            time_budget: self.time_budget.map(Duration::from_secs_f64),
+        }
+    }
+
+    // Material of the scene object at `index`, replaced when given on the command line.
+    pub fn material(
+        &self,
+        index: usize,
+        default: Arc<dyn Material>,
+    ) -> io::Result<Arc<dyn Material>> {
+        match self
+            .materials
+            .iter()
+            .rev()
+            .find(|(object, _)| *object == index)
+        {
+            Some((_, material)) => material.build(),
+            None => Ok(default),
+        }
+    }
+
+    pub fn lens_system(&self, aspect_ratio: f64) -> io::Result<Option<LensSystem>> {
+        let Some(name) = &self.lens_system else {
+            return Ok(None);
+        };
+        let physical = self.physical.unwrap_or_default();
+        LensSystem::new(
+            LensSystem::load(name)?,
+            (physical.sensor_width, physical.sensor_height),
+            aspect_ratio,
+            physical.focus_distance,
+        )
+        .map(Some)
+    }
+
+    pub fn stereo(&self) -> Option<Stereo> {
+        self.stereo.map(|layout| Stereo {
+            layout,
+            interocular: self.interocular,
+            convergence: self.convergence,
+            convergence_distance: self.convergence_distance,
+        })
+    }
+
+    pub fn tone_mapper(&self) -> ToneMapper {
+        let exposure = self
+            .physical
+            .map_or(0.0, |physical| physical.exposure_stops());
+        ToneMapper::new(self.exposure + exposure, self.tonemap)
+    }
+
+    // Numbers a per-frame output: a run of '#' is replaced by the zero-padded frame number,
+    // otherwise the number is appended to the file stem.
+    pub fn frame_path(path: &str, frame: Option<u32>) -> String {
+        let Some(frame) = frame else {
+            return path.to_string();
+        };
+        if let Some(start) = path.find('#') {
+            let digits = path[start..].chars().take_while(|c| *c == '#').count();
+            return format!(
+                "{}{frame:0digits$}{}",
+                &path[..start],
+                &path[start + digits..]
+            );
+        }
+        match path.rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() && !extension.contains('/') => {
+                format!("{stem}_{frame:04}.{extension}")
+            }
+            _ => format!("{path}_{frame:04}"),
+        }
+    }
+
+    // Accepts plain seconds or photographic fractions such as 1/60.
+    fn seconds(flag: &str, value: Option<String>) -> Result<f64, String> {
+        let value: String = Self::value(flag, value)?;
+        match value.split_once('/') {
+            Some((numerator, denominator)) => numerator
+                .parse::<f64>()
+                .and_then(|numerator| Ok(numerator / denominator.parse::<f64>()?)),
+            None => value.parse(),
+        }
+        .map_err(|err| format!("invalid value '{value}' for {flag}: {err}"))
+    }
+
+    fn pair(flag: &str, value: Option<String>) -> Result<(f64, f64), String> {
+        let value: String = Self::value(flag, value)?;
+        value
+            .split_once(',')
+            .and_then(|(a, b)| Some((a.trim().parse().ok()?, b.trim().parse().ok()?)))
+            .ok_or_else(|| {
+                format!("invalid value '{value}' for {flag} (expected two comma-separated numbers)")
+            })
+    }
+
+    fn value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String>
+    where
+        T::Err: std::fmt::Display,
+    {
+        let value = value.ok_or_else(|| format!("missing value for {flag}"))?;
+        value
+            .parse()
+            .map_err(|err| format!("invalid value '{value}' for {flag}: {err}"))
+    }
+}
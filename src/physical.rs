@@ -0,0 +1,54 @@
+// Scene units are meters; lens dimensions are given in millimeters like on a real camera.
+#[derive(Clone, Copy)]
+pub struct PhysicalCamera {
+    pub sensor_width: f64,
+    pub sensor_height: f64,
+    pub focal_length: f64,
+    pub f_number: f64,
+    pub shutter: f64,
+    pub iso: f64,
+    pub focus_distance: f64,
+}
+
+impl Default for PhysicalCamera {
+    fn default() -> Self {
+        Self {
+            sensor_width: 36.0,
+            sensor_height: 24.0,
+            focal_length: 50.0,
+            f_number: 16.0,
+            shutter: 0.01,
+            iso: 100.0,
+            focus_distance: 1.0,
+        }
+    }
+}
+
+// Exposure value at ISO 100 of the sunny 16 rule (f/16 at 1/100 s), under which a sky of unit
+// radiance is exposed like the non-physical camera renders it.
+const REFERENCE_EV100: f64 = 14.643856189774725;
+
+impl PhysicalCamera {
+    // Viewport height at the focus distance by similar triangles through the pinhole. The
+    // sensor is fitted to the image so that its longer relative side fills the frame.
+    pub fn viewport_height(&self, aspect_ratio: f64) -> f64 {
+        let sensor_height = if aspect_ratio >= self.sensor_width / self.sensor_height {
+            self.sensor_width / aspect_ratio
+        } else {
+            self.sensor_height
+        };
+        self.focus_distance * sensor_height / self.focal_length
+    }
+
+    pub fn aperture_radius(&self) -> f64 {
+        self.focal_length / self.f_number / 2.0 / 1000.0
+    }
+
+    pub fn ev100(&self) -> f64 {
+        (self.f_number * self.f_number / self.shutter * 100.0 / self.iso).log2()
+    }
+
+    pub fn exposure_stops(&self) -> f64 {
+        REFERENCE_EV100 - self.ev100()
+    }
+}
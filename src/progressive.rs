@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use crate::{
+    adaptive::PixelStats,
+    aov::{AovPixel, Aovs},
+    camera::Render,
+    film::Film,
+    sampler::SamplerKind,
+};
+
+#[derive(Clone, Copy, Default)]
+pub struct Progressive {
+    pub pass_samples: Option<i32>,
+    pub snapshot_interval: Option<Duration>,
+    pub time_budget: Option<Duration>,
+}
+
+pub struct RenderState {
+    pub film: Film,
+    pub stats: Vec<PixelStats>,
+    pub aov_pixels: Option<Vec<AovPixel>>,
+    pub passes: u32,
+    pub sampler: SamplerKind,
+    pub sampler_spp: u32,
+    pub seeds: Vec<u64>,
+    pub scene_hash: u64,
+}
+
+impl RenderState {
+    pub fn new(
+        film: Film,
+        aovs: bool,
+        sampler: SamplerKind,
+        sampler_spp: u32,
+        seeds: Vec<u64>,
+        scene_hash: u64,
+    ) -> Self {
+        let pixels = film.width * film.height;
+        Self {
+            film,
+            stats: vec![PixelStats::default(); pixels],
+            aov_pixels: aovs.then(|| vec![AovPixel::default(); pixels]),
+            passes: 0,
+            sampler,
+            sampler_spp,
+            seeds,
+            scene_hash,
+        }
+    }
+
+    pub fn check_compatible(&self, other: &RenderState) -> Result<(), String> {
+        self.check_same_frame(other)?;
+        if self.sampler != other.sampler || self.seeds != other.seeds {
+            return Err(format!(
+                "sampler {} (seeds {:?}) does not match {} (seeds {:?})",
+                self.sampler.name(),
+                self.seeds,
+                other.sampler.name(),
+                other.seeds
+            ));
+        }
+        // Stratified samples are laid out for the sample count the sampler was built with, so
+        // continuing at a different count would not reproduce an uninterrupted render.
+        if self.sampler == SamplerKind::Stratified && self.sampler_spp != other.sampler_spp {
+            return Err(format!(
+                "stratified sampler was set up for {} spp but the checkpoint uses {} spp",
+                self.sampler_spp, other.sampler_spp
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn merge(&mut self, other: RenderState) -> Result<(), String> {
+        self.check_same_frame(&other)?;
+        if let Some(seed) = other.seeds.iter().find(|seed| self.seeds.contains(seed)) {
+            return Err(format!(
+                "seed {seed} appears in more than one render, so their samples are not independent"
+            ));
+        }
+        for (pixel, other) in self.film.pixels.iter_mut().zip(&other.film.pixels) {
+            pixel.sum += other.sum.clone();
+            pixel.weight += other.weight;
+        }
+        for (stats, other) in self.stats.iter_mut().zip(&other.stats) {
+            stats.merge(other);
+        }
+        if let (Some(pixels), Some(others)) = (self.aov_pixels.as_mut(), &other.aov_pixels) {
+            for (pixel, other_pixel) in pixels.iter_mut().zip(others) {
+                pixel.merge(other_pixel);
+            }
+        }
+        self.seeds.extend(other.seeds);
+        self.passes += other.passes;
+        Ok(())
+    }
+
+    fn check_same_frame(&self, other: &RenderState) -> Result<(), String> {
+        let (a, b) = (&self.film, &other.film);
+        if (a.width, a.height) != (b.width, b.height) {
+            return Err(format!(
+                "resolution {}x{} does not match {}x{}",
+                a.width, a.height, b.width, b.height
+            ));
+        }
+        if a.filter.kind != b.filter.kind || a.filter.radius != b.filter.radius {
+            return Err(format!(
+                "filter {} (radius {}) does not match {} (radius {})",
+                a.filter.kind.name(),
+                a.filter.radius,
+                b.filter.kind.name(),
+                b.filter.radius
+            ));
+        }
+        if self.scene_hash != other.scene_hash {
+            return Err(format!(
+                "scene hash {:016x} does not match {:016x}",
+                self.scene_hash, other.scene_hash
+            ));
+        }
+        if self.aov_pixels.is_some() != other.aov_pixels.is_some() {
+            return Err("AOV settings do not match".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn render(&self) -> Render {
+        let (width, height) = (self.film.width, self.film.height);
+        let aovs = self.aov_pixels.as_ref().map(|pixels| {
+            let mut aovs = Aovs::new(width, height);
+            for (index, pixel) in pixels.iter().enumerate() {
+                aovs.set(index % width, index / width, pixel);
+            }
+            aovs
+        });
+        Render {
+            beauty: self.film.image(),
+            aovs,
+            sample_counts: self.stats.iter().map(|stats| stats.count).collect(),
+        }
+    }
+}
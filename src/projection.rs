@@ -0,0 +1,79 @@
+use crate::fingerprint::Fingerprint;
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Projection {
+    #[default]
+    Perspective,
+    Orthographic {
+        view_width: f64,
+    },
+    Equirectangular,
+    Fisheye {
+        fov: f64,
+        circular: bool,
+    },
+}
+
+impl Projection {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Projection::Perspective => "perspective",
+            Projection::Orthographic { .. } => "orthographic",
+            Projection::Equirectangular => "equirectangular",
+            Projection::Fisheye { circular: true, .. } => "fisheye",
+            Projection::Fisheye {
+                circular: false, ..
+            } => "fisheye-full",
+        }
+    }
+
+    pub fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_str(self.name());
+        match self {
+            Projection::Orthographic { view_width } => fingerprint.write_f64(*view_width),
+            Projection::Fisheye { fov, .. } => fingerprint.write_f64(*fov),
+            Projection::Perspective | Projection::Equirectangular => {}
+        }
+    }
+}
+
+impl std::str::FromStr for Projection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, parameter) = match s.split_once(':') {
+            Some((name, parameter)) => (name, Some(parameter)),
+            None => (s, None),
+        };
+        let value = |label: &str, default: f64| -> Result<f64, String> {
+            let value = match parameter {
+                Some(value) => value
+                    .parse()
+                    .map_err(|err| format!("invalid {label} '{value}': {err}"))?,
+                None => default,
+            };
+            if value <= 0.0 {
+                return Err(format!("{label} must be positive, got {value}"));
+            }
+            Ok(value)
+        };
+        match (name, parameter) {
+            ("perspective", None) => Ok(Projection::Perspective),
+            ("orthographic", _) => Ok(Projection::Orthographic {
+                view_width: value("view width", 4.0)?,
+            }),
+            ("equirectangular", None) => Ok(Projection::Equirectangular),
+            ("fisheye", _) => Ok(Projection::Fisheye {
+                fov: value("field of view", 180.0)?,
+                circular: true,
+            }),
+            ("fisheye-full", _) => Ok(Projection::Fisheye {
+                fov: value("field of view", 180.0)?,
+                circular: false,
+            }),
+            _ => Err(format!(
+                "unknown projection '{s}' (expected perspective, orthographic[:view_width], equirectangular, fisheye[:fov] or fisheye-full[:fov])"
+            )),
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use rand::{Rng, SeedableRng, rngs::SmallRng};
+
+pub trait Sampler {
+    fn start_pixel_sample(&mut self, pixel: (i32, i32), sample_index: u32);
+
+    fn get_1d(&mut self) -> f64;
+
+    fn get_2d(&mut self) -> (f64, f64);
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum SamplerKind {
+    #[default]
+    Independent,
+    Stratified,
+    Halton,
+    Sobol,
+    BlueNoise,
+}
+
+impl SamplerKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SamplerKind::Independent => "independent",
+            SamplerKind::Stratified => "stratified",
+            SamplerKind::Halton => "halton",
+            SamplerKind::Sobol => "sobol",
+            SamplerKind::BlueNoise => "bluenoise",
+        }
+    }
+
+    pub fn build(&self, samples_per_pixel: i32, seed: u64) -> Box<dyn Sampler> {
+        match self {
+            SamplerKind::Independent => Box::new(Independent::new(seed)),
+            SamplerKind::Stratified => Box::new(Stratified::new(samples_per_pixel, seed)),
+            SamplerKind::Halton => Box::new(Halton::new(seed)),
+            SamplerKind::Sobol => Box::new(Sobol::new(seed)),
+            SamplerKind::BlueNoise => Box::new(BlueNoise::new(seed)),
+        }
+    }
+}
+
+impl std::str::FromStr for SamplerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "independent" => Ok(SamplerKind::Independent),
+            "stratified" => Ok(SamplerKind::Stratified),
+            "halton" => Ok(SamplerKind::Halton),
+            "sobol" => Ok(SamplerKind::Sobol),
+            "bluenoise" => Ok(SamplerKind::BlueNoise),
+            _ => Err(format!(
+                "unknown sampler '{s}' (expected independent, stratified, halton, sobol or bluenoise)"
+            )),
+        }
+    }
+}
+
+pub fn mix(mut x: u64) -> u64 {
+    x ^= x >> 31;
+    x = x.wrapping_mul(0x7fb5d329728ea185);
+    x ^= x >> 27;
+    x = x.wrapping_mul(0x81dadef4bc2dd44d);
+    x ^ (x >> 33)
+}
+
+pub fn hash(values: &[u64]) -> u64 {
+    values
+        .iter()
+        .fold(0x9e3779b97f4a7c15, |h, &v| mix(h ^ mix(v)))
+}
+
+fn pixel_key(pixel: (i32, i32)) -> u64 {
+    ((pixel.0 as u32 as u64) << 32) | pixel.1 as u32 as u64
+}
+
+fn to_unit(bits: u32) -> f64 {
+    bits as f64 / (1u64 << 32) as f64
+}
+
+pub struct Independent {
+    seed: u64,
+    rng: SmallRng,
+}
+
+impl Independent {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: SmallRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Sampler for Independent {
+    fn start_pixel_sample(&mut self, pixel: (i32, i32), sample_index: u32) {
+        self.rng =
+            SmallRng::seed_from_u64(hash(&[self.seed, pixel_key(pixel), sample_index as u64]));
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.rng.random()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.rng.random(), self.rng.random())
+    }
+}
+
+pub struct Stratified {
+    samples_per_pixel: u32,
+    seed: u64,
+    pixel: (i32, i32),
+    sample_index: u32,
+    dimension: u64,
+    jitter: SmallRng,
+}
+
+impl Stratified {
+    pub fn new(samples_per_pixel: i32, seed: u64) -> Self {
+        Self {
+            samples_per_pixel: samples_per_pixel.max(1) as u32,
+            seed,
+            pixel: (0, 0),
+            sample_index: 0,
+            dimension: 0,
+            jitter: SmallRng::seed_from_u64(seed),
+        }
+    }
+
+    // Kensler's hashed permutation: maps i to a unique element of [0, n) for each seed.
+    fn permutation_element(mut i: u32, n: u32, seed: u32) -> u32 {
+        let mut w = n - 1;
+        w |= w >> 1;
+        w |= w >> 2;
+        w |= w >> 4;
+        w |= w >> 8;
+        w |= w >> 16;
+        loop {
+            i ^= seed;
+            i = i.wrapping_mul(0xe170893d);
+            i ^= seed >> 16;
+            i ^= (i & w) >> 4;
+            i ^= seed >> 8;
+            i = i.wrapping_mul(0x0929eb3f);
+            i ^= seed >> 23;
+            i ^= (i & w) >> 1;
+            i = i.wrapping_mul(1 | seed >> 27);
+            i = i.wrapping_mul(0x6935fa69);
+            i ^= (i & w) >> 11;
+            i = i.wrapping_mul(0x74dcb303);
+            i ^= (i & w) >> 2;
+            i = i.wrapping_mul(0x9e501cc3);
+            i ^= (i & w) >> 2;
+            i = i.wrapping_mul(0xc860a3df);
+            i &= w;
+            i ^= i >> 5;
+            if i < n {
+                return i.wrapping_add(seed) % n;
+            }
+        }
+    }
+
+    fn stratum(&mut self, count: u32) -> u32 {
+        let seed = hash(&[self.seed, pixel_key(self.pixel), self.dimension]) as u32;
+        self.dimension += 1;
+        Self::permutation_element(self.sample_index % count, count, seed)
+    }
+}
+
+impl Sampler for Stratified {
+    fn start_pixel_sample(&mut self, pixel: (i32, i32), sample_index: u32) {
+        self.pixel = pixel;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+        self.jitter =
+            SmallRng::seed_from_u64(hash(&[self.seed, pixel_key(pixel), sample_index as u64]));
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let count = self.samples_per_pixel;
+        let stratum = self.stratum(count);
+        (stratum as f64 + self.jitter.random::<f64>()) / count as f64
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let side = (self.samples_per_pixel as f64).sqrt().ceil() as u32;
+        let stratum = self.stratum(side * side);
+        let (x, y) = (stratum % side, stratum / side);
+        (
+            (x as f64 + self.jitter.random::<f64>()) / side as f64,
+            (y as f64 + self.jitter.random::<f64>()) / side as f64,
+        )
+    }
+}
+
+pub struct Halton {
+    seed: u64,
+    pixel: (i32, i32),
+    sample_index: u32,
+    dimension: usize,
+}
+
+impl Halton {
+    const PRIMES: [u32; 32] = [
+        2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89,
+        97, 101, 103, 107, 109, 113, 127, 131,
+    ];
+
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            pixel: (0, 0),
+            sample_index: 0,
+            dimension: 0,
+        }
+    }
+
+    fn radical_inverse(base: u32, mut index: u32) -> f64 {
+        let inv_base = 1.0 / base as f64;
+        let mut inv_base_n = 1.0;
+        let mut reversed = 0u64;
+        while index > 0 {
+            let next = index / base;
+            reversed = reversed * base as u64 + (index - next * base) as u64;
+            inv_base_n *= inv_base;
+            index = next;
+        }
+        (reversed as f64 * inv_base_n).min(1.0 - f64::EPSILON)
+    }
+
+    fn next(&mut self) -> f64 {
+        let dimension = self.dimension;
+        self.dimension += 1;
+        let rotation = to_unit(hash(&[self.seed, pixel_key(self.pixel), dimension as u64]) as u32);
+        let value = match Self::PRIMES.get(dimension) {
+            Some(&base) => Self::radical_inverse(base, self.sample_index),
+            None => to_unit(hash(&[
+                self.seed,
+                pixel_key(self.pixel),
+                self.sample_index as u64,
+                dimension as u64,
+            ]) as u32),
+        };
+        (value + rotation).fract()
+    }
+}
+
+impl Sampler for Halton {
+    fn start_pixel_sample(&mut self, pixel: (i32, i32), sample_index: u32) {
+        self.pixel = pixel;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.next()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.next(), self.next())
+    }
+}
+
+pub struct Sobol {
+    seed: u64,
+    pixel: (i32, i32),
+    sample_index: u32,
+    dimension: u64,
+}
+
+impl Sobol {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            pixel: (0, 0),
+            sample_index: 0,
+            dimension: 0,
+        }
+    }
+
+    fn sobol_second_dimension(mut index: u32) -> u32 {
+        let mut v = 1u32 << 31;
+        let mut result = 0;
+        while index != 0 {
+            if index & 1 != 0 {
+                result ^= v;
+            }
+            index >>= 1;
+            v ^= v >> 1;
+        }
+        result
+    }
+
+    // Laine-Karras style hash giving a nested uniform (Owen) scramble on reversed bits.
+    fn owen_scramble(x: u32, seed: u32) -> u32 {
+        let mut x = x.reverse_bits();
+        x = x.wrapping_add(seed);
+        x ^= x.wrapping_mul(0x6c50b47c);
+        x ^= x.wrapping_mul(0xb82f1e52);
+        x ^= x.wrapping_mul(0xc7afe638);
+        x ^= x.wrapping_mul(0x8d22f6e6);
+        x.reverse_bits()
+    }
+
+    fn dimension_seed(&mut self) -> u64 {
+        let seed = hash(&[self.seed, pixel_key(self.pixel), self.dimension]);
+        self.dimension += 1;
+        seed
+    }
+
+    fn shuffled_index(&self, seed: u64) -> u32 {
+        Self::owen_scramble(self.sample_index, seed as u32)
+    }
+}
+
+impl Sampler for Sobol {
+    fn start_pixel_sample(&mut self, pixel: (i32, i32), sample_index: u32) {
+        self.pixel = pixel;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let seed = self.dimension_seed();
+        let index = self.shuffled_index(seed);
+        to_unit(Self::owen_scramble(
+            index.reverse_bits(),
+            (seed >> 32) as u32,
+        ))
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let seed = self.dimension_seed();
+        let index = self.shuffled_index(seed);
+        let x = Self::owen_scramble(index.reverse_bits(), (seed >> 32) as u32);
+        let y = Self::owen_scramble(Self::sobol_second_dimension(index), mix(seed) as u32);
+        (to_unit(x), to_unit(y))
+    }
+}
+
+pub struct BlueNoise {
+    seed: u64,
+    pixel: (i32, i32),
+    sample_index: u32,
+    dimension: u64,
+}
+
+impl BlueNoise {
+    const GOLDEN: f64 = 0.618_033_988_749_894_9;
+    const R2: (f64, f64) = (0.754_877_666_246_692_7, 0.569_840_290_998_053_2);
+
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            pixel: (0, 0),
+            sample_index: 0,
+            dimension: 0,
+        }
+    }
+
+    // Interleaved gradient noise has most of its energy at high spatial frequencies, so
+    // using it to rotate each pixel's sequence spreads the error as blue noise across pixels.
+    fn interleaved_gradient_noise(&self, offset: u64) -> f64 {
+        let shift = hash(&[self.seed, offset]);
+        let x = self.pixel.0 as f64 + (shift & 0xffff) as f64;
+        let y = self.pixel.1 as f64 + ((shift >> 16) & 0xffff) as f64;
+        (52.982_918_9 * (0.067_110_56 * x + 0.005_837_15 * y).fract()).fract()
+    }
+
+    fn next_rotation(&mut self) -> f64 {
+        let rotation = self.interleaved_gradient_noise(self.dimension);
+        self.dimension += 1;
+        rotation
+    }
+}
+
+impl Sampler for BlueNoise {
+    fn start_pixel_sample(&mut self, pixel: (i32, i32), sample_index: u32) {
+        self.pixel = pixel;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let rotation = self.next_rotation();
+        (rotation + self.sample_index as f64 * Self::GOLDEN).fract()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let (rx, ry) = (self.next_rotation(), self.next_rotation());
+        let n = self.sample_index as f64;
+        ((rx + n * Self::R2.0).fract(), (ry + n * Self::R2.1).fract())
+    }
+}
@@ -1,6 +1,7 @@
-use std::sync::Arc;
+use std::{f64, sync::Arc};
 
 use crate::{
+    fingerprint::Fingerprint,
     hittable::{HitRecord, Hittable},
     interval::Interval,
     material::Material,
@@ -25,6 +26,12 @@ where
             material: Arc::new(material),
         }
     }
+
+    fn uv(point: &Point3) -> (f64, f64) {
+        let theta = (-point.y).acos();
+        let phi = (-point.z).atan2(point.x) + f64::consts::PI;
+        (phi / (2.0 * f64::consts::PI), theta / f64::consts::PI)
+    }
 }
 
 impl<T> Hittable for Sphere<T>
@@ -58,12 +65,25 @@ where
         let time = root;
         let point = ray.at(time);
         let normal = (&point - &self.center) / self.radius;
+        let uv = Self::uv(&normal);
         Some(HitRecord::new(
             point,
             time,
             normal,
-            &ray,
+            ray,
             self.material.clone(),
+            uv,
         ))
     }
+
+    fn material(&self) -> Option<Arc<dyn Material>> {
+        Some(self.material.clone())
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_str("Sphere");
+        fingerprint.write_vec3(&self.center);
+        fingerprint.write_f64(self.radius);
+        self.material.fingerprint(fingerprint);
+    }
 }
@@ -0,0 +1,118 @@
+use crate::{fingerprint::Fingerprint, image::Rect};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Eye {
+    Left,
+    Right,
+}
+
+impl Eye {
+    pub fn sign(&self) -> f64 {
+        match self {
+            Eye::Left => -1.0,
+            Eye::Right => 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Convergence {
+    #[default]
+    OffAxis,
+    ToeIn,
+}
+
+impl Convergence {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Convergence::OffAxis => "off-axis",
+            Convergence::ToeIn => "toe-in",
+        }
+    }
+}
+
+impl std::str::FromStr for Convergence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off-axis" => Ok(Convergence::OffAxis),
+            "toe-in" => Ok(Convergence::ToeIn),
+            _ => Err(format!(
+                "unknown convergence '{s}' (expected off-axis or toe-in)"
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum StereoLayout {
+    #[default]
+    SideBySide,
+    TopBottom,
+}
+
+impl StereoLayout {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StereoLayout::SideBySide => "side-by-side",
+            StereoLayout::TopBottom => "top-bottom",
+        }
+    }
+}
+
+impl std::str::FromStr for StereoLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "side-by-side" => Ok(StereoLayout::SideBySide),
+            "top-bottom" => Ok(StereoLayout::TopBottom),
+            _ => Err(format!(
+                "unknown stereo layout '{s}' (expected side-by-side or top-bottom)"
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Stereo {
+    pub layout: StereoLayout,
+    pub interocular: f64,
+    pub convergence: Convergence,
+    pub convergence_distance: f64,
+}
+
+impl Stereo {
+    // Both eyes share one film: the left eye fills the left (or top) half, the right eye the other.
+    pub fn film_size(&self, (width, height): (usize, usize)) -> (usize, usize) {
+        match self.layout {
+            StereoLayout::SideBySide => (width * 2, height),
+            StereoLayout::TopBottom => (width, height * 2),
+        }
+    }
+
+    pub fn eye(&self, (x, y): (f64, f64), (width, height): (f64, f64)) -> (Eye, (f64, f64)) {
+        match self.layout {
+            StereoLayout::SideBySide if x >= width => (Eye::Right, (x - width, y)),
+            StereoLayout::TopBottom if y >= height => (Eye::Right, (x, y - height)),
+            _ => (Eye::Left, (x, y)),
+        }
+    }
+
+    // Film area holding the view of `eye`, for images of `width` by `height` per eye.
+    pub fn eye_bounds(&self, eye: Eye, (width, height): (usize, usize)) -> Rect {
+        match (eye, self.layout) {
+            (Eye::Left, _) => Rect::new(0, 0, width, height),
+            (Eye::Right, StereoLayout::SideBySide) => Rect::new(width, 0, width * 2, height),
+            (Eye::Right, StereoLayout::TopBottom) => Rect::new(0, height, width, height * 2),
+        }
+    }
+
+    pub fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_str(self.layout.name());
+        fingerprint.write_f64(self.interocular);
+        fingerprint.write_str(self.convergence.name());
+        fingerprint.write_f64(self.convergence_distance);
+    }
+}
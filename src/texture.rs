@@ -0,0 +1,106 @@
+use std::{fs::File, io, io::BufReader, sync::Arc};
+
+use crate::{color::Color, fingerprint::Fingerprint, image::Image};
+
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f64, v: f64) -> Color;
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint);
+}
+
+pub struct Constant(Color);
+
+impl Texture for Constant {
+    fn value(&self, _u: f64, _v: f64) -> Color {
+        self.0.clone()
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_str("constant");
+        fingerprint.write_vec3(&self.0);
+    }
+}
+
+pub struct ImageTexture {
+    path: String,
+    image: Image,
+}
+
+impl Texture for ImageTexture {
+    // Nearest-pixel lookup with v pointing up, so the image appears upright on the surface.
+    fn value(&self, u: f64, v: f64) -> Color {
+        let x = (u.clamp(0.0, 1.0) * self.image.width as f64) as usize;
+        let y = ((1.0 - v.clamp(0.0, 1.0)) * self.image.height as f64) as usize;
+        self.image
+            .get(x.min(self.image.width - 1), y.min(self.image.height - 1))
+            .clone()
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_str("image");
+        fingerprint.write_str(&self.path);
+    }
+}
+
+#[derive(Clone)]
+pub enum TextureKind {
+    Constant(Color),
+    Image(String),
+}
+
+impl TextureKind {
+    // Color textures are stored sRGB-encoded in image files, while scalar maps such as roughness
+    // are stored linearly, so only the former are decoded on load.
+    pub fn build(&self, srgb: bool) -> io::Result<Arc<dyn Texture>> {
+        Ok(match self {
+            TextureKind::Constant(color) => Arc::new(Constant(color.clone())),
+            TextureKind::Image(path) => {
+                let mut image = Image::read_pnm(&mut BufReader::new(File::open(path)?))?;
+                if image.width == 0 || image.height == 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("texture '{path}' is empty"),
+                    ));
+                }
+                if srgb {
+                    for pixel in &mut image.pixels {
+                        *pixel = pixel.to_linear_space();
+                    }
+                }
+                Arc::new(ImageTexture {
+                    path: path.clone(),
+                    image,
+                })
+            }
+        })
+    }
+}
+
+impl std::str::FromStr for TextureKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = s.strip_prefix('@') {
+            return Ok(TextureKind::Image(path.to_string()));
+        }
+        let number = |value: &str| {
+            value
+                .parse::<f64>()
+                .map_err(|_| format!("invalid texture '{s}' (expected value, r,g,b or @image)"))
+        };
+        match s.split(',').collect::<Vec<_>>()[..] {
+            [value] => {
+                let value = number(value)?;
+                Ok(TextureKind::Constant(Color::new(value, value, value)))
+            }
+            [r, g, b] => Ok(TextureKind::Constant(Color::new(
+                number(r)?,
+                number(g)?,
+                number(b)?,
+            ))),
+            _ => Err(format!(
+                "invalid texture '{s}' (expected value, r,g,b or @image)"
+            )),
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{color::Color, vec3::Vec3};
+
+#[derive(Clone, Copy, Default)]
+pub enum ToneMap {
+    #[default]
+    Clamp,
+    Reinhard,
+    ExtendedReinhard {
+        white: f64,
+    },
+    Aces,
+    Agx,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ToneMapper {
+    pub exposure: f64,
+    pub operator: ToneMap,
+}
+
+impl ToneMapper {
+    pub fn new(exposure: f64, operator: ToneMap) -> Self {
+        Self { exposure, operator }
+    }
+
+    pub fn apply(&self, color: &Color) -> Color {
+        let color = color.clone() * 2f64.powf(self.exposure);
+        match self.operator {
+            ToneMap::Clamp => color,
+            ToneMap::Reinhard => Self::scale_luminance(color, |l| l / (1.0 + l)),
+            ToneMap::ExtendedReinhard { white } => {
+                Self::scale_luminance(color, |l| l * (1.0 + l / (white * white)) / (1.0 + l))
+            }
+            ToneMap::Aces => Self::map_components(color, Self::aces),
+            ToneMap::Agx => Self::agx(&color),
+        }
+    }
+
+    fn scale_luminance(color: Color, f: impl Fn(f64) -> f64) -> Color {
+        let luminance = color.luminance();
+        if luminance <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        color * (f(luminance) / luminance)
+    }
+
+    fn map_components(color: Color, f: impl Fn(f64) -> f64) -> Color {
+        Color::new(f(color.x), f(color.y), f(color.z))
+    }
+
+    fn aces(x: f64) -> f64 {
+        let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+        ((x * (a * x + b)) / (x * (c * x + d) + e)).clamp(0.0, 1.0)
+    }
+
+    fn agx(color: &Color) -> Color {
+        const INSET: [[f64; 3]; 3] = [
+            [0.842479062253094, 0.0784335999999992, 0.0792237451477643],
+            [0.0423282422610123, 0.878468636469772, 0.0791661274605434],
+            [0.0423756549057051, 0.0784336, 0.879142973793104],
+        ];
+        const OUTSET: [[f64; 3]; 3] = [
+            [1.19687900512017, -0.0980208811401368, -0.0990297440797205],
+            [-0.0528968517574562, 1.15190312990417, -0.0989611768448433],
+            [-0.0529716355144438, -0.0980434501171241, 1.15107367264116],
+        ];
+        const MIN_EV: f64 = -12.47393;
+        const MAX_EV: f64 = 4.026069;
+
+        let inset = Self::transform(&INSET, color);
+        let curve = Self::map_components(inset, |x| {
+            let x = x.max(1e-10).log2().clamp(MIN_EV, MAX_EV);
+            Self::agx_contrast((x - MIN_EV) / (MAX_EV - MIN_EV))
+        });
+        let outset = Self::transform(&OUTSET, &curve);
+        Self::map_components(outset, |x| x.max(0.0).powf(2.2))
+    }
+
+    fn agx_contrast(x: f64) -> f64 {
+        let x2 = x * x;
+        let x4 = x2 * x2;
+        15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x
+            - 0.00232
+    }
+
+    fn transform(m: &[[f64; 3]; 3], color: &Color) -> Color {
+        let row = |r: &[f64; 3]| Vec3::new(r[0], r[1], r[2]).dot(color);
+        Color::new(row(&m[0]), row(&m[1]), row(&m[2]))
+    }
+}
+
+impl std::str::FromStr for ToneMap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, white) = match s.split_once(':') {
+            Some((name, white)) => (name, Some(white)),
+            None => (s, None),
+        };
+        match (name, white) {
+            ("clamp", None) => Ok(ToneMap::Clamp),
+            ("reinhard", None) => Ok(ToneMap::Reinhard),
+            ("reinhard-extended", white) => {
+                let white: f64 = match white {
+                    Some(white) => white
+                        .parse()
+                        .map_err(|err| format!("invalid white point '{white}': {err}"))?,
+                    None => 4.0,
+                };
+                if !(white.is_finite() && white > 0.0) {
+                    return Err(format!(
+                        "white point must be finite and positive, got {white}"
+                    ));
+                }
+                Ok(ToneMap::ExtendedReinhard { white })
+            }
+            ("aces", None) => Ok(ToneMap::Aces),
+            ("agx", None) => Ok(ToneMap::Agx),
+            _ => Err(format!(
+                "unknown tone map '{s}' (expected clamp, reinhard, reinhard-extended[:white], aces or agx)"
+            )),
+        }
+    }
+}
@@ -50,6 +50,30 @@ impl Vec3 {
         }
     }
 
+    pub fn unit_from_sample((u, v): (Float, Float)) -> Self {
+        let z = 1.0 - 2.0 * u;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * std::f64::consts::PI * v;
+        Self::new(r * phi.cos(), r * phi.sin(), z)
+    }
+
+    // Concentric mapping of the unit square onto the unit disk in the xy plane.
+    pub fn unit_disk_from_sample((u, v): (Float, Float)) -> Self {
+        let (a, b) = (2.0 * u - 1.0, 2.0 * v - 1.0);
+        if a == 0.0 && b == 0.0 {
+            return Self::new(0.0, 0.0, 0.0);
+        }
+        let (r, theta) = if a.abs() > b.abs() {
+            (a, std::f64::consts::FRAC_PI_4 * (b / a))
+        } else {
+            (
+                b,
+                std::f64::consts::FRAC_PI_2 - std::f64::consts::FRAC_PI_4 * (a / b),
+            )
+        };
+        Self::new(r * theta.cos(), r * theta.sin(), 0.0)
+    }
+
     pub fn random_reflect(normal: &Self) -> Self {
         let on_unit_sphere = Self::random_unit();
         if on_unit_sphere.dot(normal) > 0.0 {